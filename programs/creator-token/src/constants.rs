@@ -2,3 +2,40 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+// Denominator for every basis point value
+#[constant]
+pub const MAX_BPS: u16 = 10_000;
+
+// Number of creator tokens listed in a single RegistryPage
+#[constant]
+pub const REGISTRY_PAGE_SIZE: u64 = 32;
+
+// Delegates an identity can hold at once
+#[constant]
+pub const MAX_DELEGATES: u8 = 5;
+
+// Delegate permission flags, combined into `Delegate::permissions`
+#[constant]
+pub const PERMISSION_PROFILE: u8 = 1 << 0;
+#[constant]
+pub const PERMISSION_WITHDRAW_FEES: u8 = 1 << 1;
+#[constant]
+pub const PERMISSION_PAUSE_BUYS: u8 = 1 << 2;
+pub const ALL_PERMISSIONS: u8 = PERMISSION_PROFILE | PERMISSION_WITHDRAW_FEES | PERMISSION_PAUSE_BUYS;
+
+// Lamports a limit order owner puts up for whoever fills the order
+#[constant]
+pub const LIMIT_ORDER_KEEPER_REWARD: u64 = 100_000;
+
+// Lamports a DCA plan pays whoever executes one of its buys
+#[constant]
+pub const DCA_EXECUTOR_TIP: u64 = 50_000;
+
+// Limits on the Token-2022 metadata of creator mints
+#[constant]
+pub const MAX_TOKEN_NAME_LEN: usize = 32;
+#[constant]
+pub const MAX_TOKEN_SYMBOL_LEN: usize = 10;
+#[constant]
+pub const MAX_TOKEN_URI_LEN: usize = 200;
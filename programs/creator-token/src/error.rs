@@ -14,10 +14,181 @@ pub enum IdentityError {
 
     #[msg("The length of the url entered exeeds 200 characters")]
     UrlTooLong,
+
+    #[msg("The identity still has a creator token, close it first")]
+    CreatorTokenStillExists,
+
+    #[msg("The identity is suspended")]
+    IdentitySuspended,
+
+    #[msg("The identity is not suspended")]
+    IdentityNotSuspended,
+
+    #[msg("Signer is neither the creator nor a delegate with the required permission")]
+    NotAuthorized,
+
+    #[msg("The identity already has the maximum number of delegates")]
+    TooManyDelegates,
+
+    #[msg("The delegate was not found on this identity")]
+    DelegateNotFound,
+
+    #[msg("Delegate permissions are empty or contain unknown flags")]
+    InvalidPermissions,
 }
 
 #[error_code]
 pub enum CustomError {
     #[msg("Overflow occured while calculating")]
     MathOverflow,
-}
\ No newline at end of file
+
+    #[msg("The account already has the current layout")]
+    AccountAlreadyMigrated,
+}
+
+#[error_code]
+pub enum CreatorTokenError {
+    #[msg("The creator token still has tokens in circulation")]
+    SupplyNotZero,
+
+    #[msg("Buying this creator token is paused")]
+    BuysPaused,
+
+    #[msg("Token name must be between 1 and 32 bytes")]
+    InvalidTokenName,
+
+    #[msg("Token symbol must be between 1 and 10 bytes")]
+    InvalidTokenSymbol,
+
+    #[msg("Token uri exceeds 200 bytes")]
+    InvalidTokenUri,
+
+    #[msg("The token account is frozen")]
+    TokenAccountFrozen,
+
+    #[msg("Royalty exceeds the maximum allowed by the platform")]
+    RoyaltyTooHigh,
+
+    #[msg("This creator token uses the holder hook, its accounts are required")]
+    HolderHookAccountsMissing,
+
+    #[msg("Non-transferable creator tokens can't charge transfer royalties")]
+    RoyaltyOnNonTransferable,
+
+    #[msg("Vesting needs a positive duration and a cliff within it")]
+    InvalidVestingSchedule,
+
+    #[msg("A creator allocation needs the vesting and vesting escrow accounts")]
+    VestingAccountsMissing,
+
+    #[msg("Nothing has vested since the last claim")]
+    NothingToClaim,
+
+    #[msg("Can't sell more than the curve has sold")]
+    InsufficientCurveSupply,
+
+    #[msg("This feature needs a Token-2022 creator token")]
+    Token2022Required,
+
+    #[msg("Early holder badges are still available, the badge accounts are required")]
+    BadgeAccountsMissing,
+
+    #[msg("A trader can't be their own referrer")]
+    SelfReferral,
+
+    #[msg("The trade's quote has expired")]
+    QuoteExpired,
+
+    #[msg("Each buy leg needs its accounts in remaining_accounts, in order")]
+    InvalidBuyLegAccounts,
+
+    #[msg("The trade moved past the accepted price")]
+    SlippageExceeded,
+
+    #[msg("Can't swap a creator token for itself")]
+    SwapSameToken,
+
+    #[msg("Limit orders need a positive amount and limit price, buys a positive max cost")]
+    InvalidLimitOrder,
+
+    #[msg("The curve price hasn't reached the order's limit price")]
+    LimitPriceNotReached,
+
+    #[msg("This limit order needs the owner's token account")]
+    LimitOrderAccountsMissing,
+
+    #[msg("The creator token still has open limit orders")]
+    OpenLimitOrders,
+
+    #[msg("DCA plans need a positive amount per interval, interval and max price")]
+    InvalidDcaPlan,
+
+    #[msg("The DCA plan is paused")]
+    DcaPlanPaused,
+
+    #[msg("The next DCA buy isn't due yet")]
+    DcaNotDue,
+
+    #[msg("The curve price is above the DCA plan's max price")]
+    DcaPriceAboveMax,
+
+    #[msg("The DCA plan balance can't cover the next buy")]
+    DcaInsufficientBalance,
+
+    #[msg("The anti-snipe window and its limits can't be negative")]
+    InvalidLaunchWindow,
+
+    #[msg("Trading of this creator token hasn't started yet")]
+    TradingNotStarted,
+
+    #[msg("Only buy_creator_token and gift_creator_token are open during the anti-snipe window")]
+    AntiSnipeWindowActive,
+
+    #[msg("The buy is larger than the anti-snipe limit per transaction")]
+    AntiSnipeBuyLimit,
+
+    #[msg("The buy takes the wallet over the anti-snipe limit per wallet")]
+    AntiSnipeWalletLimit,
+
+    #[msg("Buys in the presale and during the anti-snipe window need the wallet's launch buys account")]
+    LaunchBuysAccountMissing,
+
+    #[msg("A presale needs public trading to open after the token is created")]
+    InvalidPresale,
+
+    #[msg("Presale buys need the wallet's allowlist allocation")]
+    PresaleAllocationMissing,
+
+    #[msg("The wallet and cap aren't in the presale allowlist")]
+    InvalidPresaleProof,
+
+    #[msg("The buy takes the wallet over its presale cap")]
+    PresaleCapExceeded,
+
+    #[msg("A sell tax needs a decay period and can't exceed 100%")]
+    InvalidSellTax,
+
+    #[msg("Creator tokens with a sell tax only trade through buy_creator_token, gift_creator_token and sell_creator_token")]
+    SellTaxedToken,
+
+    #[msg("Trades of a creator token with a sell tax need the holder position account")]
+    HolderPositionMissing,
+
+    #[msg("The mint a closed token left at this address only takes a plain token with the same decimals")]
+    LeftoverMintMismatch,
+
+    #[msg("The curve needs a base price or a slope above 0")]
+    InvalidCurve,
+
+    #[msg("A new Token-2022 mint needs the group mint and holder hook accounts")]
+    Token2022AccountsMissing,
+}
+
+#[error_code]
+pub enum AdminError {
+    #[msg("Only the platform admin can perform this action")]
+    Unauthorized,
+
+    #[msg("Basis points can't exceed 10000")]
+    InvalidBps,
+}
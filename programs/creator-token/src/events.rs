@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+
+use crate::LimitOrderSide;
+
+#[event]
+pub struct EarlyHolderBadgeMinted {
+    pub mint: Pubkey,
+    pub badge_mint: Pubkey,
+    pub holder: Pubkey,
+    pub rank: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreatorTokenGifted {
+    pub mint: Pubkey,
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+    pub tokens: u64,
+    pub lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreatorTokensSwapped {
+    pub user: Pubkey,
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    // sell proceeds of `amount_in` before the trading fee
+    pub lamports: u64,
+    pub refund: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LimitOrderFilled {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub keeper: Pubkey,
+    pub mint: Pubkey,
+    pub side: LimitOrderSide,
+    pub amount: u64,
+    pub lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaExecuted {
+    pub plan: Pubkey,
+    pub owner: Pubkey,
+    pub executor: Pubkey,
+    pub mint: Pubkey,
+    pub tokens: u64,
+    pub lamports: u64,
+    pub remaining_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SellTaxPaid {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub tax: u64,
+    pub to_creator: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralFeePaid {
+    pub referrer: Pubkey,
+    pub trader: Pubkey,
+    pub mint: Pubkey,
+    pub trade_lamports: u64,
+    pub fee: u64,
+    pub referral_amount: u64,
+    pub is_buy: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralFeesClaimed {
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HolderFrozen {
+    pub mint: Pubkey,
+    pub holder_account: Pubkey,
+    pub owner: Pubkey,
+    pub admin: Pubkey,
+    pub reason: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HolderThawed {
+    pub mint: Pubkey,
+    pub holder_account: Pubkey,
+    pub owner: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,11 @@
+use anchor_lang::prelude::*;
+
+use crate::error::CreatorTokenError;
+
+// Trades can carry the deadline of the quote they were signed against, stale ones are rejected
+pub fn check_deadline(expires_at: Option<i64>) -> Result<()> {
+    if let Some(expires_at) = expires_at {
+        require!(Clock::get()?.unix_timestamp <= expires_at, CreatorTokenError::QuoteExpired);
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{token_2022::Token2022, token_interface::{
+    group_member_pointer_initialize, initialize_mint2, metadata_pointer_initialize, mint_close_authority_initialize,
+    non_transferable_mint_initialize, spl_token_2022::{extension::ExtensionType, state::Mint as MintState},
+    transfer_fee_initialize, transfer_hook_initialize, GroupMemberPointerInitialize, InitializeMint2, MetadataPointerInitialize,
+    MintCloseAuthorityInitialize, NonTransferableMintInitialize, TransferFeeInitialize, TransferHookInitialize,
+}};
+
+use crate::helpers::create_pda_account;
+
+pub struct CreatorMintAccounts<'info> {
+    pub payer: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub mint_authority: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+pub struct CreatorMintOptions {
+    pub decimals: u8,
+    // 0 leaves the TransferFeeConfig extension off
+    pub transfer_fee_bps: u16,
+    // Program called by Token-2022 on every transfer
+    pub transfer_hook_program: Option<Pubkey>,
+    // Soulbound, tokens can only be minted and burned by the curve
+    pub non_transferable: bool,
+    // Member of the platform group, the member itself is written once the mint exists
+    pub group_member: bool,
+}
+
+// Creates the creator mint at its PDA with the extensions picked at creation.
+// Anchor's `init` can't add extensions conditionally so the account is laid out here.
+// The mint authority PDA holds every authority, the metadata itself is written afterwards.
+// Legacy SPL Token mints get no extensions, the caller leaves the optional ones off.
+pub fn create_creator_mint(
+    accounts: &CreatorMintAccounts,
+    mint_seeds: &[&[u8]],
+    options: &CreatorMintOptions,
+) -> Result<()> {
+    let mint_authority = accounts.mint_authority.key();
+
+    let is_token_2022 = *accounts.token_program.key == Token2022::id();
+
+    let mut extension_types = vec![];
+    if is_token_2022 {
+        extension_types.push(ExtensionType::MintCloseAuthority);
+        extension_types.push(ExtensionType::MetadataPointer);
+    }
+    if options.transfer_fee_bps > 0 {
+        extension_types.push(ExtensionType::TransferFeeConfig);
+    }
+    if options.transfer_hook_program.is_some() {
+        extension_types.push(ExtensionType::TransferHook);
+    }
+    if options.non_transferable {
+        extension_types.push(ExtensionType::NonTransferable);
+    }
+    if options.group_member {
+        extension_types.push(ExtensionType::GroupMemberPointer);
+    }
+    let space = ExtensionType::try_calculate_account_len::<MintState>(&extension_types)?;
+
+    // Token-2022 grows the mint when the group member is added, fund that space up front
+    let funded_space = if options.group_member {
+        let mut with_member = extension_types.clone();
+        with_member.push(ExtensionType::TokenGroupMember);
+        ExtensionType::try_calculate_account_len::<MintState>(&with_member)?
+    } else {
+        space
+    };
+
+    create_pda_account(
+        accounts.payer.clone(),
+        accounts.mint.clone(),
+        accounts.system_program.clone(),
+        Rent::get()?.minimum_balance(funded_space),
+        space as u64,
+        accounts.token_program.key,
+        mint_seeds,
+    )?;
+
+    // extensions have to be initialized before the mint itself
+    if is_token_2022 {
+        mint_close_authority_initialize(
+            CpiContext::new(
+                accounts.token_program.clone(),
+                MintCloseAuthorityInitialize { token_program_id: accounts.token_program.clone(), mint: accounts.mint.clone() },
+            ),
+            Some(&mint_authority),
+        )?;
+
+        metadata_pointer_initialize(
+            CpiContext::new(
+                accounts.token_program.clone(),
+                MetadataPointerInitialize { token_program_id: accounts.token_program.clone(), mint: accounts.mint.clone() },
+            ),
+            Some(mint_authority),
+            Some(accounts.mint.key()),
+        )?;
+    }
+
+    if options.transfer_fee_bps > 0 {
+        transfer_fee_initialize(
+            CpiContext::new(
+                accounts.token_program.clone(),
+                TransferFeeInitialize { token_program_id: accounts.token_program.clone(), mint: accounts.mint.clone() },
+            ),
+            Some(&mint_authority),
+            Some(&mint_authority),
+            options.transfer_fee_bps,
+            u64::MAX,
+        )?;
+    }
+
+    if options.transfer_hook_program.is_some() {
+        transfer_hook_initialize(
+            CpiContext::new(
+                accounts.token_program.clone(),
+                TransferHookInitialize { token_program_id: accounts.token_program.clone(), mint: accounts.mint.clone() },
+            ),
+            Some(mint_authority),
+            options.transfer_hook_program,
+        )?;
+    }
+
+    if options.group_member {
+        group_member_pointer_initialize(
+            CpiContext::new(
+                accounts.token_program.clone(),
+                GroupMemberPointerInitialize { token_program_id: accounts.token_program.clone(), mint: accounts.mint.clone() },
+            ),
+            Some(mint_authority),
+            Some(accounts.mint.key()),
+        )?;
+    }
+
+    if options.non_transferable {
+        non_transferable_mint_initialize(CpiContext::new(
+            accounts.token_program.clone(),
+            NonTransferableMintInitialize { token_program_id: accounts.token_program.clone(), mint: accounts.mint.clone() },
+        ))?;
+    }
+
+    initialize_mint2(
+        CpiContext::new(accounts.token_program.clone(), InitializeMint2 { mint: accounts.mint.clone() }),
+        options.decimals,
+        &mint_authority,
+        Some(&mint_authority),
+    )
+}
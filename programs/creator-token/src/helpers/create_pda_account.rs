@@ -0,0 +1,35 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer},
+};
+
+// Creates the account at a PDA of this program like Anchor's `init` does. create_account fails
+// once anyone has sent lamports to the address, so a pre-funded account is topped up to
+// `lamports`, allocated and assigned instead.
+pub fn create_pda_account<'info>(
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    lamports: u64,
+    space: u64,
+    owner: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let signer_seeds = [signer_seeds];
+    let current_lamports = account.lamports();
+    if current_lamports == 0 {
+        let accounts = CreateAccount { from: payer, to: account };
+        return create_account(CpiContext::new_with_signer(system_program, accounts, &signer_seeds), lamports, space, owner);
+    }
+
+    if lamports > current_lamports {
+        let accounts = Transfer { from: payer, to: account.clone() };
+        transfer(CpiContext::new(system_program.clone(), accounts), lamports - current_lamports)?;
+    }
+
+    let accounts = Allocate { account_to_allocate: account.clone() };
+    allocate(CpiContext::new_with_signer(system_program.clone(), accounts, &signer_seeds), space)?;
+
+    let accounts = Assign { account_to_assign: account };
+    assign(CpiContext::new_with_signer(system_program, accounts, &signer_seeds), owner)
+}
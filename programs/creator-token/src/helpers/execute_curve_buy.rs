@@ -0,0 +1,278 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::token_interface::{self, Mint, MintTo};
+
+use crate::{
+    error::{CreatorTokenError, CustomError},
+    events::{EarlyHolderBadgeMinted, ReferralFeePaid},
+    helpers::{
+        get_buying_cost, get_trade_fee, mint_early_holder_badge, pay_trade_fee, record_holder_mint, verify_presale_allocation,
+        BadgeAccounts, BadgeDetails, HolderHookAccounts, TradeFee,
+    },
+    Config, CreatorToken, HolderPosition, LaunchBuys, PresaleAllocation, Referrer,
+};
+
+// Where the lamports of a buy come from. Wallets pay through the system program, escrows owned
+// by this program (limit orders, DCA plans) have their lamports moved directly
+pub enum BuyFunding<'a, 'info> {
+    Wallet(AccountInfo<'info>),
+    Escrow(AccountInfo<'info>),
+    // a system-owned PDA of this program (another token's vault), pays through the system program
+    // signed with its seeds
+    Vault(AccountInfo<'info>, &'a [&'a [&'a [u8]]]),
+}
+
+// How a route treats the presale and the anti-snipe window
+pub enum LaunchAccess {
+    // only buys once public trading is open and the anti-snipe window is over
+    OpenOnly,
+    // takes part in both, the wallet's totals are tracked on its LaunchBuys account
+    Tracked(Option<PresaleAllocation>),
+}
+
+// Accounts of an early holder badge, only routes that pass them hand badges out
+pub struct BadgeInputs<'info> {
+    pub badge_mint: Option<AccountInfo<'info>>,
+    pub badge_ata: Option<AccountInfo<'info>>,
+    pub badge_token_program: Option<AccountInfo<'info>>,
+    pub badge_mint_bump: Option<u8>,
+    pub associated_token_program: AccountInfo<'info>,
+}
+
+pub struct CurveBuyAccounts<'a, 'info> {
+    // signer paying for whatever the buy opens (holder record, badge, launch buys, position)
+    pub payer: AccountInfo<'info>,
+    pub funding: BuyFunding<'a, 'info>,
+    // wallet the tokens are minted to
+    pub recipient: AccountInfo<'info>,
+    pub recipient_ata: AccountInfo<'info>,
+    pub creator_token: &'a mut Account<'info, CreatorToken>,
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub mint_authority: AccountInfo<'info>,
+    pub vault: AccountInfo<'info>,
+    pub config: &'a Config,
+    pub fee_vault: AccountInfo<'info>,
+    pub referrer: Option<&'a mut Account<'info, Referrer>>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub holder_hook_program: Option<AccountInfo<'info>>,
+    pub hook_authority: Option<AccountInfo<'info>>,
+    pub holder_record: Option<AccountInfo<'info>>,
+    pub holder_stats: Option<AccountInfo<'info>>,
+    pub hook_authority_bump: Option<u8>,
+    pub launch_buys: Option<&'a mut Account<'info, LaunchBuys>>,
+    pub launch_buys_bump: Option<u8>,
+    pub position: Option<&'a mut Account<'info, HolderPosition>>,
+    pub position_bump: Option<u8>,
+    pub badge: Option<BadgeInputs<'info>>,
+}
+
+pub struct CurveBuy {
+    // curve cost, paid into the vault
+    pub total_price: u64,
+    pub fee: TradeFee,
+}
+
+fn tracked_launch_buys<'a, 'info>(accounts: &'a mut CurveBuyAccounts<'_, 'info>) -> Result<&'a mut Account<'info, LaunchBuys>> {
+    let creator_token = accounts.creator_token.key();
+    let buyer = accounts.recipient.key();
+    let (Some(launch_buys), Some(bump)) = (accounts.launch_buys.as_deref_mut(), accounts.launch_buys_bump) else {
+        return err!(CreatorTokenError::LaunchBuysAccountMissing);
+    };
+    launch_buys.creator_token = creator_token;
+    launch_buys.buyer = buyer;
+    launch_buys.bump = bump;
+    Ok(launch_buys)
+}
+
+// The presale and anti-snipe limits, the limits lift on their own once the window is over
+fn check_launch_window(accounts: &mut CurveBuyAccounts, access: LaunchAccess, tokens: u64, now: i64) -> Result<()> {
+    let LaunchAccess::Tracked(presale) = access else {
+        return accounts.creator_token.check_open_for_buys(now);
+    };
+
+    let creator_token = &accounts.creator_token;
+    let (anti_snipe_max_buy, anti_snipe_max_wallet) = (creator_token.anti_snipe_max_buy, creator_token.anti_snipe_max_wallet);
+    if now < creator_token.trading_starts_at {
+        // before public trading only allowlisted wallets get in, each up to its cap
+        require!(creator_token.has_presale(), CreatorTokenError::TradingNotStarted);
+        let allocation = presale.ok_or(error!(CreatorTokenError::PresaleAllocationMissing))?;
+        verify_presale_allocation(&creator_token.presale_merkle_root, &accounts.recipient.key(), allocation.cap, &allocation.proof)?;
+
+        let launch_buys = tracked_launch_buys(accounts)?;
+        launch_buys.presale_amount = launch_buys.presale_amount.checked_add(tokens).ok_or(error!(CustomError::MathOverflow))?;
+        require!(launch_buys.presale_amount <= allocation.cap, CreatorTokenError::PresaleCapExceeded);
+    } else if now < creator_token.anti_snipe_ends_at {
+        require!(anti_snipe_max_buy == 0 || tokens <= anti_snipe_max_buy, CreatorTokenError::AntiSnipeBuyLimit);
+        if anti_snipe_max_wallet > 0 {
+            let launch_buys = tracked_launch_buys(accounts)?;
+            launch_buys.amount = launch_buys.amount.checked_add(tokens).ok_or(error!(CustomError::MathOverflow))?;
+            require!(launch_buys.amount <= anti_snipe_max_wallet, CreatorTokenError::AntiSnipeWalletLimit);
+        }
+    }
+    Ok(())
+}
+
+// Moves the curve cost into the vault and the trading fee out to the fee vault and referrer
+fn pay_for_buy(accounts: &mut CurveBuyAccounts, total_price: u64, fee: &TradeFee) -> Result<()> {
+    let (from, signer_seeds) = match &accounts.funding {
+        BuyFunding::Wallet(from) => (from.clone(), &[][..]),
+        BuyFunding::Vault(from, signer_seeds) => (from.clone(), *signer_seeds),
+        BuyFunding::Escrow(from) => {
+            let spent = total_price.checked_add(fee.total()).ok_or(error!(CustomError::MathOverflow))?;
+            from.sub_lamports(spent)?;
+            accounts.vault.add_lamports(total_price)?;
+            accounts.fee_vault.add_lamports(fee.platform)?;
+            if let Some(referrer) = accounts.referrer.as_deref_mut() {
+                referrer.add_lamports(fee.referral)?;
+                referrer.total_earned = referrer.total_earned.checked_add(fee.referral).ok_or(error!(CustomError::MathOverflow))?;
+            } else {
+                accounts.fee_vault.add_lamports(fee.referral)?;
+            }
+            return Ok(());
+        }
+    };
+
+    let transfer_accounts = Transfer { from: from.clone(), to: accounts.vault.clone() };
+    transfer(CpiContext::new_with_signer(accounts.system_program.clone(), transfer_accounts, signer_seeds), total_price)?;
+    pay_trade_fee(
+        fee,
+        from,
+        accounts.fee_vault.clone(),
+        accounts.referrer.as_deref_mut(),
+        accounts.system_program.clone(),
+        signer_seeds,
+    )
+}
+
+// The first distinct buyers get a badge, an existing badge mint marks a repeat buyer
+fn award_badge<'info>(accounts: &mut CurveBuyAccounts<'_, 'info>, badge: &BadgeInputs<'info>, mint_authority_seeds: &[&[u8]]) -> Result<()> {
+    let creator_token = &accounts.creator_token;
+    if creator_token.early_holder_count >= creator_token.early_holder_limit {
+        return Ok(());
+    }
+    let (Some(badge_mint), Some(badge_ata), Some(badge_token_program), Some(badge_mint_bump)) =
+        (&badge.badge_mint, &badge.badge_ata, &badge.badge_token_program, badge.badge_mint_bump)
+    else {
+        return err!(CreatorTokenError::BadgeAccountsMissing);
+    };
+    if !badge_mint.data_is_empty() {
+        return Ok(());
+    }
+
+    let rank = creator_token.early_holder_count + 1;
+    let timestamp = Clock::get()?.unix_timestamp;
+    let creator_token_key = creator_token.key();
+    let holder_key = accounts.recipient.key();
+    let badge_mint_seeds: &[&[u8]] = &[b"badge", creator_token_key.as_ref(), holder_key.as_ref(), &[badge_mint_bump]];
+    mint_early_holder_badge(
+        &BadgeAccounts {
+            payer: accounts.payer.clone(),
+            holder: accounts.recipient.clone(),
+            badge_mint: badge_mint.clone(),
+            badge_ata: badge_ata.clone(),
+            mint_authority: accounts.mint_authority.clone(),
+            token_program: badge_token_program.clone(),
+            associated_token_program: badge.associated_token_program.clone(),
+            system_program: accounts.system_program.clone(),
+        },
+        badge_mint_seeds,
+        mint_authority_seeds,
+        &BadgeDetails {
+            creator: creator_token.creator,
+            creator_mint: creator_token.mint,
+            rank,
+            timestamp,
+        },
+    )?;
+
+    emit!(EarlyHolderBadgeMinted {
+        mint: creator_token.mint,
+        badge_mint: badge_mint.key(),
+        holder: holder_key,
+        rank,
+        timestamp,
+    });
+    accounts.creator_token.early_holder_count = rank;
+    Ok(())
+}
+
+// Buys `tokens` off the curve for `recipient`, shared by every buy route so pricing, fees, holder
+// tracking, sell tax positions, badges and the launch limits stay the same whichever way a buy comes in.
+// `max_cost` caps the curve cost plus the trading fee
+pub fn execute_curve_buy(mut accounts: CurveBuyAccounts, tokens: u64, max_cost: Option<u64>, access: LaunchAccess) -> Result<CurveBuy> {
+    let now = Clock::get()?.unix_timestamp;
+    check_launch_window(&mut accounts, access, tokens, now)?;
+
+    let creator_token = &accounts.creator_token;
+    let total_price = get_buying_cost(
+        tokens,
+        creator_token.curve_supply(accounts.mint.supply)?,
+        creator_token.base_price,
+        creator_token.slope,
+        accounts.mint.decimals,
+    )?;
+    let fee = get_trade_fee(accounts.config, total_price, accounts.referrer.is_some())?;
+    if let Some(max_cost) = max_cost {
+        require!(total_price + fee.total() <= max_cost, CreatorTokenError::SlippageExceeded);
+    }
+
+    pay_for_buy(&mut accounts, total_price, &fee)?;
+    if let Some(referrer) = &accounts.referrer {
+        emit!(ReferralFeePaid {
+            referrer: referrer.authority,
+            trader: accounts.payer.key(),
+            mint: accounts.mint.key(),
+            trade_lamports: total_price,
+            fee: fee.total(),
+            referral_amount: fee.referral,
+            is_buy: true,
+            timestamp: now,
+        });
+    }
+
+    let mint_accounts = MintTo {
+        authority: accounts.mint_authority.clone(),
+        mint: accounts.mint.to_account_info(),
+        to: accounts.recipient_ata.clone(),
+    };
+    let creator_token_key = accounts.creator_token.key();
+    let mint_authority_seeds: &[&[&[u8]]] = &[&[b"mint_authority", creator_token_key.as_ref(), &[accounts.creator_token.mint_authority_bump]]];
+    token_interface::mint_to(CpiContext::new_with_signer(accounts.token_program.clone(), mint_accounts, mint_authority_seeds), tokens)?;
+
+    // the transfer hook doesn't run on mints, report the recipient's new balance
+    if accounts.creator_token.has_holder_hook {
+        let hook = HolderHookAccounts::new(
+            accounts.holder_hook_program.clone(),
+            accounts.hook_authority.clone(),
+            accounts.holder_record.clone(),
+            accounts.holder_stats.clone(),
+            accounts.hook_authority_bump,
+        )?;
+        accounts.creator_token.holder_count = record_holder_mint(
+            &hook,
+            accounts.payer.clone(),
+            accounts.mint.to_account_info(),
+            accounts.recipient.clone(),
+            accounts.system_program.clone(),
+            tokens,
+        )?;
+    }
+
+    // the sell tax decays from the weighted time the recipient's tokens were bought. Routes without
+    // a position mint untracked tokens, those pay the full tax
+    if let (true, Some(position), Some(position_bump)) =
+        (accounts.creator_token.has_sell_tax(), accounts.position.as_deref_mut(), accounts.position_bump)
+    {
+        let recipient = accounts.recipient.key();
+        position.creator_token = creator_token_key;
+        position.holder = recipient;
+        position.bump = position_bump;
+        position.record_buy(tokens, now)?;
+    }
+
+    if let Some(badge) = accounts.badge.take() {
+        award_badge(&mut accounts, &badge, mint_authority_seeds[0])?;
+    }
+
+    Ok(CurveBuy { total_price, fee })
+}
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+use crate::error::CustomError;
+
+// Price of the next whole token at `current_supply_base`, lamports per whole token
+pub fn get_marginal_price(
+    current_supply_base: u64,   // base units
+    base_price_per_token: u64,  // lamports per whole token
+    slope_per_token: u64,       // lamports per whole token supply
+    decimals: u8,               // mint.decimals
+) -> Result<u64> {
+    let d: u128 = 10u128.checked_pow(decimals as u32).ok_or(error!(CustomError::MathOverflow))?;
+
+    // b + m * s0 / D
+    let price = (slope_per_token as u128)
+        .checked_mul(current_supply_base as u128)
+        .and_then(|x| x.checked_div(d))
+        .and_then(|x| x.checked_add(base_price_per_token as u128))
+        .ok_or(error!(CustomError::MathOverflow))?;
+    u64::try_from(price).map_err(|_| error!(CustomError::MathOverflow))
+}
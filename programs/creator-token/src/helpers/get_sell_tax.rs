@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_BPS, error::CustomError};
+
+// Holding-period tax on the `sold` base units that returned `token_return` lamports. The `tracked`
+// ones start at `tax_bps` at acquisition and decay linearly to 0 over `decay_seconds`, the rest
+// came in some other way (a transfer) and pay the full `tax_bps`
+pub fn get_sell_tax(token_return: u64, sold: u64, tracked: u64, tax_bps: u16, decay_seconds: i64, held_seconds: i64) -> Result<u64> {
+    if sold == 0 || tax_bps == 0 {
+        return Ok(0);
+    }
+    let tracked = tracked.min(sold);
+    let untracked = sold - tracked;
+    let (remaining, decay) = if decay_seconds <= 0 || held_seconds >= decay_seconds {
+        (0, 1)
+    } else {
+        ((decay_seconds - held_seconds.max(0)) as u128, decay_seconds as u128)
+    };
+
+    // return * (tracked * remaining + untracked * decay) / sold / decay * tax_bps / MAX_BPS, one division to keep precision
+    let tax = (tracked as u128)
+        .checked_mul(remaining)
+        .and_then(|x| x.checked_add((untracked as u128) * decay))
+        .and_then(|x| x.checked_mul(token_return as u128))
+        .and_then(|x| x.checked_mul(tax_bps as u128))
+        .and_then(|x| x.checked_div((sold as u128) * decay * (MAX_BPS as u128)))
+        .ok_or(error!(CustomError::MathOverflow))?;
+    u64::try_from(tax).map_err(|_| error!(CustomError::MathOverflow))
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::{CreatorTokenError, CustomError}, helpers::get_buying_cost};
+
+// Floor of the square root, Newton's method from a power of two above the root
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = 1u128 << (128 - n.leading_zeros()).div_ceil(2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+// Inverse of get_buying_cost, the most base units `lamports` buys at the current supply
+pub fn get_tokens_for_lamports(
+    lamports: u64,              // lamports to spend
+    current_supply_base: u64,   // base units
+    base_price_per_token: u64,  // lamports per whole token
+    slope_per_token: u64,       // lamports per whole token supply
+    decimals: u8,               // mint.decimals
+) -> Result<u64> {
+    let d: u128 = 10u128.checked_pow(decimals as u32).ok_or(error!(CustomError::MathOverflow))?;
+    let d2 = d.checked_mul(d).ok_or(error!(CustomError::MathOverflow))?;
+
+    let s0 = current_supply_base as u128;
+    let b = base_price_per_token as u128;
+    let m = slope_per_token as u128;
+
+    // get_buying_cost floors numer / D^2, so t is affordable while
+    // numer(t) = (b*D + m*s0) * t + m * t^2 / 2  <=  (lamports + 1) * D^2 - 1
+    let budget = (lamports as u128 + 1).checked_mul(d2).ok_or(error!(CustomError::MathOverflow))? - 1;
+    let linear = b.checked_mul(d).and_then(|x| x.checked_add(m.checked_mul(s0)?)).ok_or(error!(CustomError::MathOverflow))?;
+    // a curve with neither a base price nor a slope gives tokens away, there's no amount to stop at
+    require!(linear > 0 || m > 0, CreatorTokenError::InvalidCurve);
+
+    let estimate = if m == 0 {
+        budget.checked_div(linear)
+    } else {
+        // positive root of m/2 * t^2 + linear * t - budget
+        linear
+            .checked_mul(linear)
+            .and_then(|x| x.checked_add(m.checked_mul(2)?.checked_mul(budget)?))
+            .and_then(|discriminant| (isqrt(discriminant) - linear).checked_div(m))
+    }
+    .ok_or(error!(CustomError::MathOverflow))?;
+
+    // the estimate ignores the rounding of t^2 / 2, it is at most a token or two off
+    let mut tokens = u64::try_from(estimate).unwrap_or(u64::MAX);
+    while tokens > 0 && get_buying_cost(tokens, current_supply_base, base_price_per_token, slope_per_token, decimals)? > lamports {
+        tokens -= 1;
+    }
+    while tokens < u64::MAX
+        && get_buying_cost(tokens + 1, current_supply_base, base_price_per_token, slope_per_token, decimals)? <= lamports
+    {
+        tokens += 1;
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::helpers::top_up_rent;
+
+// Grows a program account created with an older, shorter layout to `new_len` bytes, the
+// payer covers the extra rent and the new bytes are zeroed. Returns false if it was already that long
+pub fn grow_account<'info>(
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    new_len: usize,
+) -> Result<bool> {
+    if account.data_len() >= new_len {
+        return Ok(false);
+    }
+
+    top_up_rent(payer, account.clone(), system_program, new_len)?;
+    account.resize(new_len)?;
+    Ok(true)
+}
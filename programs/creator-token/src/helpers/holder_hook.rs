@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use holder_hook::{cpi::{self, accounts::{RecordBurn, RecordMint}}, HolderStats};
+
+use crate::error::CreatorTokenError;
+
+// Holder hook accounts a curve instruction passes for tokens that have the hook,
+// they are optional on the instruction since older tokens don't
+pub struct HolderHookAccounts<'info> {
+    pub holder_hook_program: AccountInfo<'info>,
+    pub hook_authority: AccountInfo<'info>,
+    pub holder_record: AccountInfo<'info>,
+    pub holder_stats: AccountInfo<'info>,
+    pub hook_authority_bump: u8,
+}
+
+impl<'info> HolderHookAccounts<'info> {
+    pub fn new(
+        holder_hook_program: Option<AccountInfo<'info>>,
+        hook_authority: Option<AccountInfo<'info>>,
+        holder_record: Option<AccountInfo<'info>>,
+        holder_stats: Option<AccountInfo<'info>>,
+        hook_authority_bump: Option<u8>,
+    ) -> Result<Self> {
+        match (holder_hook_program, hook_authority, holder_record, holder_stats, hook_authority_bump) {
+            (Some(holder_hook_program), Some(hook_authority), Some(holder_record), Some(holder_stats), Some(hook_authority_bump)) => {
+                Ok(Self { holder_hook_program, hook_authority, holder_record, holder_stats, hook_authority_bump })
+            }
+            _ => err!(CreatorTokenError::HolderHookAccountsMissing),
+        }
+    }
+
+    // Holder count after the last reported change, mirrored on the creator token
+    fn holder_count(&self) -> Result<u64> {
+        let holder_stats = HolderStats::try_deserialize(&mut &self.holder_stats.try_borrow_data()?[..])?;
+        Ok(holder_stats.holder_count)
+    }
+}
+
+// Reports tokens minted by the curve to `owner`, the hook doesn't see mints.
+// Returns the mint's holder count afterwards
+pub fn record_holder_mint<'info>(
+    hook: &HolderHookAccounts<'info>,
+    payer: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let accounts = RecordMint {
+        hook_authority: hook.hook_authority.clone(),
+        payer,
+        mint,
+        owner,
+        holder_record: hook.holder_record.clone(),
+        holder_stats: hook.holder_stats.clone(),
+        system_program,
+    };
+    let hook_authority_seeds: &[&[&[u8]]] = &[&[b"hook_authority", &[hook.hook_authority_bump]]];
+    let cpi_context = CpiContext::new_with_signer(hook.holder_hook_program.clone(), accounts, hook_authority_seeds);
+    cpi::record_mint(cpi_context, amount)?;
+    hook.holder_count()
+}
+
+// Reports tokens burned by the curve from `owner`, the hook doesn't see burns.
+// Returns the mint's holder count afterwards
+pub fn record_holder_burn<'info>(
+    hook: &HolderHookAccounts<'info>,
+    mint: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let accounts = RecordBurn {
+        hook_authority: hook.hook_authority.clone(),
+        mint,
+        owner,
+        holder_record: hook.holder_record.clone(),
+        holder_stats: hook.holder_stats.clone(),
+    };
+    let hook_authority_seeds: &[&[&[u8]]] = &[&[b"hook_authority", &[hook.hook_authority_bump]]];
+    let cpi_context = CpiContext::new_with_signer(hook.holder_hook_program.clone(), accounts, hook_authority_seeds);
+    cpi::record_burn(cpi_context, amount)?;
+    hook.holder_count()
+}
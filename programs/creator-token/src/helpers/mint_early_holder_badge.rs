@@ -0,0 +1,129 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{self, Create},
+    token_interface::{
+        self, set_authority,
+        spl_pod::optional_keys::OptionalNonZeroPubkey,
+        spl_token_2022::instruction::AuthorityType,
+        spl_token_metadata_interface::state::{Field, TokenMetadata},
+        token_metadata_initialize, token_metadata_update_field, MintTo, SetAuthority, TokenMetadataInitialize,
+        TokenMetadataUpdateField,
+    },
+};
+
+use crate::helpers::{create_creator_mint, top_up_rent, CreatorMintAccounts, CreatorMintOptions};
+
+pub struct BadgeAccounts<'info> {
+    pub payer: AccountInfo<'info>,
+    pub holder: AccountInfo<'info>,
+    pub badge_mint: AccountInfo<'info>,
+    pub badge_ata: AccountInfo<'info>,
+    // the creator token's mint authority PDA, also mints the badges
+    pub mint_authority: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+pub struct BadgeDetails {
+    pub creator: Pubkey,
+    pub creator_mint: Pubkey,
+    pub rank: u16,
+    pub timestamp: i64,
+}
+
+// Mints a soulbound Token-2022 NFT to the holder, paid for by the payer. The mint authority is
+// dropped after the single token is minted so the supply stays at 1.
+pub fn mint_early_holder_badge(
+    accounts: &BadgeAccounts,
+    badge_mint_seeds: &[&[u8]],
+    mint_authority_seeds: &[&[u8]],
+    details: &BadgeDetails,
+) -> Result<()> {
+    create_creator_mint(
+        &CreatorMintAccounts {
+            payer: accounts.payer.clone(),
+            mint: accounts.badge_mint.clone(),
+            mint_authority: accounts.mint_authority.clone(),
+            token_program: accounts.token_program.clone(),
+            system_program: accounts.system_program.clone(),
+        },
+        badge_mint_seeds,
+        &CreatorMintOptions {
+            decimals: 0,
+            transfer_fee_bps: 0,
+            transfer_hook_program: None,
+            non_transferable: true,
+            group_member: false,
+        },
+    )?;
+
+    let additional_metadata = vec![
+        ("creator".to_string(), details.creator.to_string()),
+        ("creator_mint".to_string(), details.creator_mint.to_string()),
+        ("rank".to_string(), details.rank.to_string()),
+        ("timestamp".to_string(), details.timestamp.to_string()),
+    ];
+    let token_metadata = TokenMetadata {
+        update_authority: OptionalNonZeroPubkey(accounts.mint_authority.key()),
+        mint: accounts.badge_mint.key(),
+        name: format!("Early Holder #{}", details.rank),
+        symbol: "EARLY".to_string(),
+        uri: String::new(),
+        additional_metadata: additional_metadata.clone(),
+    };
+
+    // fund the whole metadata once, the fields below are added one by one
+    top_up_rent(
+        accounts.payer.clone(),
+        accounts.badge_mint.clone(),
+        accounts.system_program.clone(),
+        accounts.badge_mint.data_len() + token_metadata.tlv_size_of()?,
+    )?;
+
+    let signer_seeds = [mint_authority_seeds];
+    let cpi_accounts = TokenMetadataInitialize {
+        program_id: accounts.token_program.clone(),
+        metadata: accounts.badge_mint.clone(),
+        update_authority: accounts.mint_authority.clone(),
+        mint_authority: accounts.mint_authority.clone(),
+        mint: accounts.badge_mint.clone(),
+    };
+    let cpi_context = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, &signer_seeds);
+    token_metadata_initialize(cpi_context, token_metadata.name, token_metadata.symbol, token_metadata.uri)?;
+
+    for (key, value) in additional_metadata {
+        let cpi_accounts = TokenMetadataUpdateField {
+            program_id: accounts.token_program.clone(),
+            metadata: accounts.badge_mint.clone(),
+            update_authority: accounts.mint_authority.clone(),
+        };
+        let cpi_context = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, &signer_seeds);
+        token_metadata_update_field(cpi_context, Field::Key(key), value)?;
+    }
+
+    let cpi_accounts = Create {
+        payer: accounts.payer.clone(),
+        associated_token: accounts.badge_ata.clone(),
+        authority: accounts.holder.clone(),
+        mint: accounts.badge_mint.clone(),
+        system_program: accounts.system_program.clone(),
+        token_program: accounts.token_program.clone(),
+    };
+    associated_token::create(CpiContext::new(accounts.associated_token_program.clone(), cpi_accounts))?;
+
+    let cpi_accounts = MintTo {
+        authority: accounts.mint_authority.clone(),
+        mint: accounts.badge_mint.clone(),
+        to: accounts.badge_ata.clone(),
+    };
+    let cpi_context = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, &signer_seeds);
+    token_interface::mint_to(cpi_context, 1)?;
+
+    let cpi_accounts = SetAuthority {
+        current_authority: accounts.mint_authority.clone(),
+        account_or_mint: accounts.badge_mint.clone(),
+    };
+    let cpi_context = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, &signer_seeds);
+    set_authority(cpi_context, AuthorityType::MintTokens, None)
+}
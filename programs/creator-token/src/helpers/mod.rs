@@ -1,5 +1,35 @@
+pub mod check_deadline;
+pub mod create_creator_mint;
+pub mod create_pda_account;
+pub mod execute_curve_buy;
 pub mod get_buying_cost;
+pub mod get_marginal_price;
+pub mod get_sell_tax;
 pub mod get_selling_return;
+pub mod get_tokens_for_lamports;
+pub mod grow_account;
+pub mod holder_hook;
+pub mod mint_early_holder_badge;
+pub mod presale_allowlist;
+pub mod token_index_seed;
+pub mod top_up_rent;
+pub mod trade_fee;
+pub mod validate_token_metadata;
 
+pub use check_deadline::check_deadline;
+pub use create_creator_mint::*;
+pub use create_pda_account::create_pda_account;
+pub use execute_curve_buy::*;
 pub use get_buying_cost::get_buying_cost;
-pub use get_selling_return::get_selling_return;
\ No newline at end of file
+pub use get_marginal_price::get_marginal_price;
+pub use get_sell_tax::get_sell_tax;
+pub use get_selling_return::get_selling_return;
+pub use get_tokens_for_lamports::get_tokens_for_lamports;
+pub use grow_account::grow_account;
+pub use holder_hook::*;
+pub use mint_early_holder_badge::*;
+pub use presale_allowlist::*;
+pub use token_index_seed::token_index_seed;
+pub use top_up_rent::top_up_rent;
+pub use trade_fee::*;
+pub use validate_token_metadata::*;
@@ -0,0 +1,19 @@
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+use crate::error::CreatorTokenError;
+
+// sha256(wallet || cap as little endian u64), 40 bytes so it can't pass for an inner node
+pub fn presale_leaf(wallet: &Pubkey, cap: u64) -> [u8; 32] {
+    hashv(&[wallet.as_ref(), &cap.to_le_bytes()]).to_bytes()
+}
+
+// `proof` runs from the leaf up. Inner nodes hash their children in sorted order, so proofs
+// don't carry left/right flags
+pub fn verify_presale_allocation(root: &[u8; 32], wallet: &Pubkey, cap: u64, proof: &[[u8; 32]]) -> Result<()> {
+    let node = proof.iter().fold(presale_leaf(wallet, cap), |node, sibling| {
+        let (left, right) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        hashv(&[&left, &right]).to_bytes()
+    });
+    require!(node == *root, CreatorTokenError::InvalidPresaleProof);
+    Ok(())
+}
@@ -0,0 +1,10 @@
+// Seed segment that tells apart the creator tokens of a single identity.
+// Index 0 maps to an empty segment so the first token keeps the original
+// `[prefix, identity_proof]` addresses it was created with.
+pub fn token_index_seed(token_index: u16) -> Vec<u8> {
+    if token_index == 0 {
+        Vec::new()
+    } else {
+        token_index.to_le_bytes().to_vec()
+    }
+}
@@ -0,0 +1,19 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+// Tops up `account` so it stays rent exempt at `new_len` bytes, used before
+// Token-2022 grows a mint to fit variable length extensions
+pub fn top_up_rent<'info>(
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(new_len);
+    let current_lamports = account.lamports();
+    if required_lamports <= current_lamports {
+        return Ok(());
+    }
+
+    let accounts = Transfer { from: payer, to: account };
+    transfer(CpiContext::new(system_program, accounts), required_lamports - current_lamports)
+}
@@ -0,0 +1,50 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+use crate::{constants::MAX_BPS, error::CustomError, Config, Referrer};
+
+// Trading fee on a single curve trade, the referrer's share is carved out of it
+pub struct TradeFee {
+    pub platform: u64,
+    pub referral: u64,
+}
+
+impl TradeFee {
+    pub fn total(&self) -> u64 {
+        self.platform + self.referral
+    }
+}
+
+fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    u64::try_from(amount as u128 * bps as u128 / MAX_BPS as u128).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+pub fn get_trade_fee(config: &Config, trade_lamports: u64, has_referrer: bool) -> Result<TradeFee> {
+    let total = bps_of(trade_lamports, config.trade_fee_bps)?;
+    let referral = if has_referrer { bps_of(total, config.referral_share_bps)? } else { 0 };
+    Ok(TradeFee { platform: total - referral, referral })
+}
+
+// Moves the fee out of `from`, the referral share accrues on the Referrer account until claimed
+pub fn pay_trade_fee<'info>(
+    fee: &TradeFee,
+    from: AccountInfo<'info>,
+    fee_vault: AccountInfo<'info>,
+    referrer: Option<&mut Account<'info, Referrer>>,
+    system_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if fee.platform > 0 {
+        let accounts = Transfer { from: from.clone(), to: fee_vault };
+        transfer(CpiContext::new_with_signer(system_program.clone(), accounts, signer_seeds), fee.platform)?;
+    }
+
+    if let Some(referrer) = referrer {
+        if fee.referral > 0 {
+            let accounts = Transfer { from, to: referrer.to_account_info() };
+            transfer(CpiContext::new_with_signer(system_program, accounts, signer_seeds), fee.referral)?;
+            referrer.total_earned = referrer.total_earned.checked_add(fee.referral).ok_or(error!(CustomError::MathOverflow))?;
+        }
+    }
+
+    Ok(())
+}
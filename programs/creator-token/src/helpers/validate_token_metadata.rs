@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{MAX_TOKEN_NAME_LEN, MAX_TOKEN_SYMBOL_LEN, MAX_TOKEN_URI_LEN},
+    error::CreatorTokenError,
+};
+
+// Lengths are in bytes, what the metadata account stores, not characters
+pub fn validate_token_name(name: &str) -> Result<()> {
+    require!(!name.is_empty() && name.len() <= MAX_TOKEN_NAME_LEN, CreatorTokenError::InvalidTokenName);
+    Ok(())
+}
+
+pub fn validate_token_symbol(symbol: &str) -> Result<()> {
+    require!(!symbol.is_empty() && symbol.len() <= MAX_TOKEN_SYMBOL_LEN, CreatorTokenError::InvalidTokenSymbol);
+    Ok(())
+}
+
+pub fn validate_token_uri(uri: &str) -> Result<()> {
+    require!(uri.len() <= MAX_TOKEN_URI_LEN, CreatorTokenError::InvalidTokenUri);
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, AssociatedToken, Create},
+    token::Token,
+    token_interface::{Mint, Token2022},
+};
+
+use holder_hook::program::HolderHook;
+
+use crate::{
+    error::{CreatorTokenError, IdentityError},
+    helpers::{check_deadline, execute_curve_buy, token_index_seed, BuyFunding, CurveBuyAccounts, LaunchAccess},
+    Config, CreatorToken, Identity, IdentityStatus,
+};
+
+// Accounts each leg passes through `remaining_accounts`, in this order: identity_proof, creator_token,
+// mint, mint_authority, vault, buyer_ata, holder_record, holder_stats. creator_token is written for
+// tokens with the holder hook. Legs of tokens without the
+// holder hook can pass any account for the last two.
+pub const BUY_LEG_ACCOUNTS: usize = 8;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BuyLeg {
+    pub creator: Pubkey,
+    pub token_index: u16,
+    pub tokens_to_buy: u64,
+    // Most lamports the leg may take from the buyer, trading fee included
+    pub max_cost: u64,
+}
+
+#[derive(Accounts)]
+pub struct BuyMany<'info> {
+    #[account(mut)]
+    pub buyer : Signer<'info>,
+
+    #[account(seeds=[b"config"], bump=config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds=[b"fee_vault"], bump=config.fee_vault_bump)]
+    pub fee_vault: SystemAccount<'info>,
+
+    // legs can mix creator tokens of both token programs
+    pub token_program : Program<'info, Token>,
+    pub token_2022_program : Program<'info, Token2022>,
+    pub associated_token_program : Program<'info, AssociatedToken>,
+    pub system_program : Program<'info, System>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+}
+
+// Buys every leg or none of them. Early holder badges are only handed out by buy_creator_token and gift_creator_token.
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, BuyMany<'info>>, legs: Vec<BuyLeg>, expires_at: Option<i64>) -> Result<()> {
+    check_deadline(expires_at)?;
+    require!(
+        !legs.is_empty() && ctx.remaining_accounts.len() == legs.len() * BUY_LEG_ACCOUNTS,
+        CreatorTokenError::InvalidBuyLegAccounts
+    );
+
+    for (index, (leg, accounts)) in legs.iter().zip(ctx.remaining_accounts.chunks(BUY_LEG_ACCOUNTS)).enumerate() {
+        // the leg's own error is returned so clients keep its code, the log names the leg
+        buy_leg(&ctx, leg, accounts).inspect_err(|_| msg!("Buy leg {} failed", index))?;
+    }
+
+    Ok(())
+}
+
+// Same check as a `seeds` constraint with a stored bump
+fn require_pda(account: &AccountInfo, seeds: &[&[u8]]) -> Result<()> {
+    let expected = Pubkey::create_program_address(seeds, &crate::ID)
+        .map_err(|_| error!(anchor_lang::error::ErrorCode::ConstraintSeeds))?;
+    require_keys_eq!(account.key(), expected, anchor_lang::error::ErrorCode::ConstraintSeeds);
+    Ok(())
+}
+
+fn buy_leg<'info>(ctx: &Context<'_, '_, 'info, 'info, BuyMany<'info>>, leg: &BuyLeg, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+    let [identity_info, creator_token_info, mint_info, mint_authority, vault, buyer_ata, holder_record, holder_stats] = accounts else {
+        return err!(CreatorTokenError::InvalidBuyLegAccounts);
+    };
+
+    // the checks BuyToken runs through its account constraints
+    let index_seed = token_index_seed(leg.token_index);
+    let identity_proof = Account::<Identity>::try_from(identity_info)?;
+    require_pda(identity_info, &[b"identity", leg.creator.as_ref(), &[identity_proof.bump]])?;
+    require!(identity_proof.status == IdentityStatus::Active, IdentityError::IdentitySuspended);
+
+    let mut creator_token = Account::<CreatorToken>::try_from(creator_token_info)?;
+    require_pda(creator_token_info, &[b"creator_token", identity_info.key.as_ref(), &index_seed, &[creator_token.bump]])?;
+    require!(!creator_token.buys_paused, CreatorTokenError::BuysPaused);
+
+    let mint = InterfaceAccount::<Mint>::try_from(mint_info)?;
+    require_pda(mint_info, &[b"owner", identity_info.key.as_ref(), &index_seed, &[creator_token.mint_bump]])?;
+    require_pda(mint_authority, &[b"mint_authority", creator_token_info.key.as_ref(), &[creator_token.mint_authority_bump]])?;
+    require_pda(vault, &[b"vault", identity_info.key.as_ref(), &index_seed, &[creator_token.vault_bump]])?;
+
+    let token_program = if creator_token.token_program == Token2022::id() {
+        ctx.accounts.token_2022_program.to_account_info()
+    } else {
+        ctx.accounts.token_program.to_account_info()
+    };
+
+    // the associated token program checks the ATA address
+    let accounts = Create {
+        payer: ctx.accounts.buyer.to_account_info(),
+        associated_token: buyer_ata.clone(),
+        authority: ctx.accounts.buyer.to_account_info(),
+        mint: mint_info.clone(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        token_program: token_program.clone(),
+    };
+    create_idempotent(CpiContext::new(ctx.accounts.associated_token_program.to_account_info(), accounts))?;
+
+    // launch limits aren't tracked for legs, they only go through once the anti-snipe window is over
+    execute_curve_buy(
+        CurveBuyAccounts {
+            payer: ctx.accounts.buyer.to_account_info(),
+            funding: BuyFunding::Wallet(ctx.accounts.buyer.to_account_info()),
+            recipient: ctx.accounts.buyer.to_account_info(),
+            recipient_ata: buyer_ata.clone(),
+            creator_token: &mut creator_token,
+            mint: &mint,
+            mint_authority: mint_authority.clone(),
+            vault: vault.clone(),
+            config: &ctx.accounts.config,
+            fee_vault: ctx.accounts.fee_vault.to_account_info(),
+            referrer: None,
+            token_program,
+            system_program: ctx.accounts.system_program.to_account_info(),
+            holder_hook_program: ctx.accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+            hook_authority: ctx.accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+            holder_record: Some(holder_record.clone()),
+            holder_stats: Some(holder_stats.clone()),
+            hook_authority_bump: ctx.bumps.hook_authority,
+            launch_buys: None,
+            launch_buys_bump: None,
+            position: None,
+            position_bump: None,
+            badge: None,
+        },
+        leg.tokens_to_buy,
+        Some(leg.max_cost),
+        LaunchAccess::OpenOnly,
+    )?;
+
+    if creator_token.has_holder_hook {
+        creator_token.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
@@ -1,16 +1,32 @@
-use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken, 
-    token_2022::MintTo, token_interface::{self, Mint, TokenAccount, TokenInterface}
+    token_interface::{Mint, Token2022, TokenAccount, TokenInterface}
 };
 
+use holder_hook::program::HolderHook;
+
 use crate::{
-    helpers::get_buying_cost::get_buying_cost,
+    error::{CreatorTokenError, IdentityError},
+    helpers::{check_deadline, execute_curve_buy, token_index_seed, BadgeInputs, BuyFunding, CurveBuyAccounts, LaunchAccess},
+    Config,
     CreatorToken, 
-    Identity
+    HolderPosition,
+    LaunchBuys,
+    Referrer,
+    Identity,
+    IdentityStatus
 };
 
+// Allowlist entry of a presale buyer, `cap` is in base units
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PresaleAllocation {
+    pub cap: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
 #[derive(Accounts)]
+#[instruction(token_index: u16)]
 pub struct BuyToken<'info> {
     #[account(mut)]
     pub buyer : Signer<'info>,
@@ -18,15 +34,21 @@ pub struct BuyToken<'info> {
     /// CHECK: Needed to derive proper accounts for validation
     pub creator : AccountInfo<'info>,
 
-    #[account(seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    #[account(
+        seeds=[b"identity", creator.key().as_ref()], 
+        bump=identity_proof.bump,
+        constraint = identity_proof.status == IdentityStatus::Active @ IdentityError::IdentitySuspended
+    )]
     pub identity_proof : Account<'info, Identity>,
 
-    #[account(mut, seeds=[b"vault", identity_proof.key().as_ref()], bump=creator_token.vault_bump)]
+    #[account(mut, seeds=[b"vault", identity_proof.key().as_ref(), &token_index_seed(token_index)], bump=creator_token.vault_bump)]
     pub vault : SystemAccount<'info>,
 
     #[account(
-        seeds=[b"creator_token", identity_proof.key().as_ref()], 
-        bump=creator_token.bump
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
+        bump=creator_token.bump,
+        constraint = !creator_token.buys_paused @ CreatorTokenError::BuysPaused
     )]
     pub creator_token : Account<'info, CreatorToken>,
 
@@ -34,12 +56,12 @@ pub struct BuyToken<'info> {
         mut,
         mint::authority = mint_authority,
         mint::freeze_authority = mint_authority,
-        seeds = [b"owner", identity_proof.key().as_ref()],
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
         bump=creator_token.mint_bump
     )]
     pub mint: InterfaceAccount<'info, Mint>,
 
-    #[account(seeds=[b"mint_authority"], bump=creator_token.mint_authority_bump)]
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
     pub mint_authority: SystemAccount<'info>,
 
     #[account(
@@ -47,47 +69,127 @@ pub struct BuyToken<'info> {
         payer=buyer,
         associated_token::mint = mint,
         associated_token::authority = buyer,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program,
+        constraint = !buyer_ata.is_frozen() @ CreatorTokenError::TokenAccountFrozen
     )]
     pub buyer_ata: InterfaceAccount<'info, TokenAccount>,
 
     pub system_program : Program<'info, System>,
+    #[account(address = creator_token.token_program)]
     pub token_program : Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the buyer, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Buyer's early holder badge mint, created in the handler on a qualifying first buy
+    #[account(mut, seeds=[b"badge", creator_token.key().as_ref(), buyer.key().as_ref()], bump)]
+    pub badge_mint: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Buyer's ATA for the badge, the associated token program checks the address
+    #[account(mut)]
+    pub badge_ata: Option<UncheckedAccount<'info>>,
+
+    // badges are Token-2022 NFTs whichever program the creator token uses
+    pub badge_token_program: Option<Program<'info, Token2022>>,
+
+    #[account(seeds=[b"config"], bump=config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds=[b"fee_vault"], bump=config.fee_vault_bump)]
+    pub fee_vault: SystemAccount<'info>,
+
+    // affiliate that brought the buyer in, takes a share of the trading fee
+    #[account(
+        mut,
+        seeds=[b"referrer", referrer.authority.as_ref()],
+        bump=referrer.bump,
+        constraint = referrer.authority != buyer.key() @ CreatorTokenError::SelfReferral
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+
+    // what the buyer took in the presale and the anti-snipe window, only needed until the window ends
+    #[account(
+        init_if_needed,
+        payer=buyer,
+        seeds=[b"launch_buys", creator_token.key().as_ref(), buyer.key().as_ref()],
+        space=8+LaunchBuys::INIT_SPACE,
+        bump
+    )]
+    pub launch_buys: Option<Account<'info, LaunchBuys>>,
+
+    // when the buyer's tokens were bought, only needed for tokens with a sell tax
+    #[account(
+        init_if_needed,
+        payer=buyer,
+        seeds=[b"position", creator_token.key().as_ref(), buyer.key().as_ref()],
+        space=8+HolderPosition::INIT_SPACE,
+        bump
+    )]
+    pub position: Option<Account<'info, HolderPosition>>,
 }
 
-pub fn handler(ctx: Context<BuyToken>, tokens_to_buy: u64) -> Result<()> {
-    // calculate tokens to send
-    let current_supply: u64 = ctx.accounts.mint.supply;
-    let base_price: u64 = ctx.accounts.creator_token.base_price;
-    let slope: u64 = ctx.accounts.creator_token.slope;
-    let decimals: u8 = ctx.accounts.mint.decimals;  
-    let total_price: u64 = get_buying_cost(
-        tokens_to_buy,              // base units (u64)
-        current_supply,             // base units (u64)
-        base_price,                 // lamports per whole token (u64)
-        slope,                      // lamports per whole token (u64)
-        decimals                    // decimals (u8)
-    )?;
+pub fn handler(
+    ctx: Context<BuyToken>,
+    _token_index: u16,
+    tokens_to_buy: u64,
+    expires_at: Option<i64>,
+    presale: Option<PresaleAllocation>,
+) -> Result<()> {
+    check_deadline(expires_at)?;
+    // buys are the only route that adds to the position, without it the tax would never decay
+    require!(
+        !ctx.accounts.creator_token.has_sell_tax() || ctx.accounts.position.is_some(),
+        CreatorTokenError::HolderPositionMissing
+    );
 
-    // Store the received lamports to vault
-    let accounts = Transfer { 
-        from : ctx.accounts.buyer.to_account_info(),
-        to: ctx.accounts.vault.to_account_info(),
-    };
-    let context = CpiContext::new(ctx.accounts.system_program.to_account_info(), accounts );
-    transfer(context, total_price)?;
-
-    // cpi the creator tokens to the user
-    let program = ctx.accounts.token_program.to_account_info();
-    let accounts = MintTo {
-        authority : ctx.accounts.mint_authority.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        to: ctx.accounts.buyer_ata.to_account_info(),
-    };
-    let mint_authority_seeds: &[&[&[u8]]]=  &[&[b"mint_authority", &[ctx.accounts.creator_token.mint_authority_bump]]];
-    
-    let cpi_context = CpiContext::new_with_signer(program, accounts, mint_authority_seeds);
-    token_interface::mint_to(cpi_context,tokens_to_buy)
-}
\ No newline at end of file
+    let accounts = &mut *ctx.accounts;
+    execute_curve_buy(
+        CurveBuyAccounts {
+            payer: accounts.buyer.to_account_info(),
+            funding: BuyFunding::Wallet(accounts.buyer.to_account_info()),
+            recipient: accounts.buyer.to_account_info(),
+            recipient_ata: accounts.buyer_ata.to_account_info(),
+            creator_token: &mut accounts.creator_token,
+            mint: &accounts.mint,
+            mint_authority: accounts.mint_authority.to_account_info(),
+            vault: accounts.vault.to_account_info(),
+            config: &accounts.config,
+            fee_vault: accounts.fee_vault.to_account_info(),
+            referrer: accounts.referrer.as_mut(),
+            token_program: accounts.token_program.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            holder_hook_program: accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+            hook_authority: accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+            holder_record: accounts.holder_record.as_ref().map(|account| account.to_account_info()),
+            holder_stats: accounts.holder_stats.as_ref().map(|account| account.to_account_info()),
+            hook_authority_bump: ctx.bumps.hook_authority,
+            launch_buys: accounts.launch_buys.as_mut(),
+            launch_buys_bump: ctx.bumps.launch_buys,
+            position: accounts.position.as_mut(),
+            position_bump: ctx.bumps.position,
+            badge: Some(BadgeInputs {
+                badge_mint: accounts.badge_mint.as_ref().map(|account| account.to_account_info()),
+                badge_ata: accounts.badge_ata.as_ref().map(|account| account.to_account_info()),
+                badge_token_program: accounts.badge_token_program.as_ref().map(|program| program.to_account_info()),
+                badge_mint_bump: ctx.bumps.badge_mint,
+                associated_token_program: accounts.associated_token_program.to_account_info(),
+            }),
+        },
+        tokens_to_buy,
+        None,
+        LaunchAccess::Tracked(presale),
+    )?;
+    Ok(())
+}
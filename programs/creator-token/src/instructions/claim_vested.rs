@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{self, burn, Burn, Mint, MintTo, TokenAccount, TokenInterface},
+};
+
+use holder_hook::program::HolderHook;
+
+use crate::{
+    error::{CreatorTokenError, CustomError},
+    helpers::{record_holder_mint, token_index_seed, HolderHookAccounts},
+    CreatorToken, CreatorVesting, Identity,
+};
+
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct ClaimVested<'info> {
+    #[account(mut)]
+    pub creator : Signer<'info>,
+
+    #[account(seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Account<'info, Identity>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
+        bump=creator_token.bump
+    )]
+    pub creator_token : Account<'info, CreatorToken>,
+
+    #[account(
+        mut,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    #[account(mut, seeds=[b"vesting", creator_token.key().as_ref()], bump=vesting.bump)]
+    pub vesting : Account<'info, CreatorVesting>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vesting,
+        associated_token::token_program = token_program
+    )]
+    pub vesting_escrow : InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        associated_token::mint = mint,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program
+    )]
+    pub creator_ata : InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program : Interface<'info, TokenInterface>,
+    pub system_program : Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the creator, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+}
+
+// The escrowed tokens are burned and re-minted to the creator instead of transferred, so
+// claims skip transfer fees and the transfer hook and also work on soulbound mints.
+// Supply doesn't change, the allocation stays outside the curve supply either way.
+pub fn handler(ctx: Context<ClaimVested>, _token_index: u16) -> Result<()> {
+    let vesting = &ctx.accounts.vesting;
+    let vested = vesting
+        .vested_amount(Clock::get()?.unix_timestamp)
+        .ok_or(error!(CustomError::MathOverflow))?;
+    let claimable = vested.checked_sub(vesting.claimed_amount).ok_or(error!(CustomError::MathOverflow))?;
+    require!(claimable > 0, CreatorTokenError::NothingToClaim);
+
+    let accounts = Burn {
+        authority: ctx.accounts.vesting.to_account_info(),
+        from: ctx.accounts.vesting_escrow.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+    };
+    let creator_token_key = ctx.accounts.creator_token.key();
+    let vesting_seeds: &[&[&[u8]]] = &[&[b"vesting", creator_token_key.as_ref(), &[ctx.accounts.vesting.bump]]];
+    let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, vesting_seeds);
+    burn(cpi_context, claimable)?;
+
+    let accounts = MintTo {
+        authority: ctx.accounts.mint_authority.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.creator_ata.to_account_info(),
+    };
+    let mint_authority_seeds: &[&[&[u8]]] = &[&[b"mint_authority", creator_token_key.as_ref(), &[ctx.accounts.creator_token.mint_authority_bump]]];
+    let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, mint_authority_seeds);
+    token_interface::mint_to(cpi_context, claimable)?;
+
+    // the escrow isn't tracked by the holder hook, only the creator's side is reported
+    if ctx.accounts.creator_token.has_holder_hook {
+        let hook = HolderHookAccounts::new(
+            ctx.accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+            ctx.accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.holder_record.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.holder_stats.as_ref().map(|account| account.to_account_info()),
+            ctx.bumps.hook_authority,
+        )?;
+        ctx.accounts.creator_token.holder_count = record_holder_mint(
+            &hook,
+            ctx.accounts.creator.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.creator.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            claimable,
+        )?;
+    }
+
+    let vesting = &mut ctx.accounts.vesting;
+    vesting.claimed_amount = vested;
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::IdentityError, Identity, TokenCounter};
+
+#[derive(Accounts)]
+pub struct CloseCreatorIdentity<'info> {
+    #[account(mut)]
+    pub creator : Signer<'info>,
+
+    // The identity can't be closed while any of its creator tokens is alive
+    #[account(
+        mut,
+        close = creator,
+        seeds=[b"identity", creator.key().as_ref()],
+        bump=identity_proof.bump,
+        constraint = identity_proof.active_tokens == 0 @ IdentityError::CreatorTokenStillExists
+    )]
+    pub identity_proof : Account<'info, Identity>,
+
+    // Keeps the token count after the identity is gone
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + TokenCounter::INIT_SPACE,
+        seeds = [b"token_counter", creator.key().as_ref()],
+        bump
+    )]
+    pub token_counter : Account<'info, TokenCounter>,
+
+    pub system_program : Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<CloseCreatorIdentity>) -> Result<()> {
+    // identity_proof is closed by the `close` constraint
+    let token_counter = &mut ctx.accounts.token_counter;
+    token_counter.creator = ctx.accounts.creator.key();
+    token_counter.token_count = ctx.accounts.identity_proof.token_count;
+    token_counter.bump = ctx.bumps.token_counter;
+
+    Ok(())
+}
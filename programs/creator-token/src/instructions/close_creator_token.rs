@@ -0,0 +1,171 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::token_interface::{
+    self,
+    spl_token_2022::{extension::{mint_close_authority::MintCloseAuthority, BaseStateWithExtensions, StateWithExtensions}, state::Mint as MintState},
+    burn, Burn, CloseAccount, Mint, TokenAccount, TokenInterface,
+};
+
+use holder_hook::{cpi::accounts::CloseMintAccounts, program::HolderHook};
+
+use crate::{error::{CreatorTokenError, CustomError}, helpers::token_index_seed, CreatorToken, CreatorVesting, Identity};
+
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct CloseCreatorToken<'info> {
+    #[account(mut)]
+    pub creator : Signer<'info>,
+
+    #[account(mut, seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Account<'info, Identity>,
+
+    #[account(
+        mut,
+        close = creator,
+        has_one = mint,
+        has_one = vault,
+        constraint = creator_token.open_limit_orders == 0 @ CreatorTokenError::OpenLimitOrders,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
+        bump=creator_token.bump
+    )]
+    pub creator_token : Account<'info, CreatorToken>,
+
+    // Token-2022 only lets the close authority close a mint once its supply is 0, checked in the
+    // handler after the unclaimed allocation is burned
+    #[account(
+        mut,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    #[account(mut, seeds=[b"vault", identity_proof.key().as_ref(), &token_index_seed(token_index)], bump=creator_token.vault_bump)]
+    pub vault : SystemAccount<'info>,
+
+    #[account(address = creator_token.token_program)]
+    pub token_program : Interface<'info, TokenInterface>,
+    pub system_program : Program<'info, System>,
+
+    // Vesting schedule and escrow of the creator allocation, settled and closed with the token.
+    // Only needed when the token has an allocation
+    #[account(mut, close = creator, seeds=[b"vesting", creator_token.key().as_ref()], bump=vesting.bump)]
+    pub vesting : Option<Account<'info, CreatorVesting>>,
+
+    // The token program checks it belongs to `vesting` and holds this mint when burning from it
+    #[account(mut)]
+    pub vesting_escrow : Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // The mint's holder hook accounts, closed with it for tokens that have the hook
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts the close from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Validation account of the mint's transfer hook, validated by the holder hook program
+    #[account(mut)]
+    pub extra_account_meta_list: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+}
+
+pub fn handler(ctx: Context<CloseCreatorToken>, token_index: u16) -> Result<()> {
+    let creator_token_key = ctx.accounts.creator_token.key();
+    let mint_authority_seeds: &[&[&[u8]]] = &[&[b"mint_authority", creator_token_key.as_ref(), &[ctx.accounts.creator_token.mint_authority_bump]]];
+
+    // burn what the creator hasn't claimed yet and close the escrow, the vesting account is closed
+    // by its `close` constraint
+    if ctx.accounts.creator_token.creator_allocation > 0 {
+        let (Some(vesting), Some(vesting_escrow)) = (ctx.accounts.vesting.as_ref(), ctx.accounts.vesting_escrow.as_ref()) else {
+            return err!(CreatorTokenError::VestingAccountsMissing);
+        };
+        let vesting_seeds: &[&[&[u8]]] = &[&[b"vesting", creator_token_key.as_ref(), &[vesting.bump]]];
+
+        let accounts = Burn {
+            authority: vesting.to_account_info(),
+            from: vesting_escrow.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, vesting_seeds);
+        burn(cpi_context, vesting_escrow.amount)?;
+
+        let accounts = CloseAccount {
+            account: vesting_escrow.to_account_info(),
+            destination: ctx.accounts.creator.to_account_info(),
+            authority: vesting.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, vesting_seeds);
+        token_interface::close_account(cpi_context)?;
+
+        ctx.accounts.mint.reload()?;
+    }
+    require!(ctx.accounts.mint.supply == 0, CreatorTokenError::SupplyNotZero);
+
+    // sweep whatever is left in the vault (rounding dust) back to the creator
+    let vault_balance = ctx.accounts.vault.lamports();
+    if vault_balance > 0 {
+        let transfer_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.creator.to_account_info()
+        };
+        let identity_proof_key = ctx.accounts.identity_proof.key();
+        let index_seed = token_index_seed(token_index);
+        let vault_seeds: &[&[&[u8]]] = &[&[b"vault", identity_proof_key.as_ref(), &index_seed, &[ctx.accounts.creator_token.vault_bump]]];
+        let cpi_context = CpiContext::new_with_signer(ctx.accounts.system_program.to_account_info(), transfer_accounts, vault_seeds);
+        transfer(cpi_context, vault_balance)?;
+    }
+
+    // close the hook accounts too, otherwise a token created later at the same mint address
+    // couldn't register with the hook again
+    if ctx.accounts.creator_token.has_holder_hook {
+        let (Some(holder_hook_program), Some(hook_authority), Some(extra_account_meta_list), Some(holder_stats), Some(hook_authority_bump)) = (
+            ctx.accounts.holder_hook_program.as_ref(),
+            ctx.accounts.hook_authority.as_ref(),
+            ctx.accounts.extra_account_meta_list.as_ref(),
+            ctx.accounts.holder_stats.as_ref(),
+            ctx.bumps.hook_authority,
+        ) else {
+            return err!(CreatorTokenError::HolderHookAccountsMissing);
+        };
+        let accounts = CloseMintAccounts {
+            hook_authority: hook_authority.to_account_info(),
+            destination: ctx.accounts.creator.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            extra_account_meta_list: extra_account_meta_list.to_account_info(),
+            holder_stats: holder_stats.to_account_info(),
+        };
+        let hook_authority_seeds: &[&[&[u8]]] = &[&[b"hook_authority", &[hook_authority_bump]]];
+        let cpi_context = CpiContext::new_with_signer(holder_hook_program.to_account_info(), accounts, hook_authority_seeds);
+        holder_hook::cpi::close_mint_accounts(cpi_context)?;
+    }
+
+    // close the mint, mint rent goes back to the creator. Mints without a close authority (legacy
+    // SPL Token ones and those created before the extension) stay behind with the PDA as their only
+    // authority, create_creator_token takes them over when a token is created at this index again
+    let has_close_authority = {
+        let mint_info = ctx.accounts.mint.to_account_info();
+        let mint_data = mint_info.try_borrow_data()?;
+        StateWithExtensions::<MintState>::unpack(&mint_data)?.get_extension::<MintCloseAuthority>().is_ok()
+    };
+    if has_close_authority {
+        let accounts = CloseAccount {
+            account: ctx.accounts.mint.to_account_info(),
+            destination: ctx.accounts.creator.to_account_info(),
+            authority: ctx.accounts.mint_authority.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, mint_authority_seeds);
+        token_interface::close_account(cpi_context)?;
+    }
+
+    ctx.accounts.identity_proof.active_tokens = ctx.accounts.identity_proof.active_tokens
+        .checked_sub(1)
+        .ok_or(CustomError::MathOverflow)?;
+
+    Ok(())
+
+    // creator_token is closed by the `close` constraint
+}
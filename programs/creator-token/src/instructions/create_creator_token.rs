@@ -1,81 +1,354 @@
-use anchor_lang::prelude::*;
-use anchor_spl::{ associated_token::AssociatedToken, token_2022::MintTo, token_interface::{self, Mint, TokenAccount, TokenInterface}};
+use anchor_lang::{prelude::*, solana_program::program_option::COption};
+use anchor_spl::{
+    associated_token::{self, AssociatedToken, Create},
+    token_2022::Token2022,
+    token_interface::{
+        spl_token_2022::{extension::{BaseStateWithExtensions, StateWithExtensions}, state::Mint as MintState},
+        spl_pod::optional_keys::OptionalNonZeroPubkey,
+        spl_token_metadata_interface::state::TokenMetadata,
+        self as token_interface, token_member_initialize, token_metadata_initialize, MintTo, TokenInterface,
+        TokenMemberInitialize, TokenMetadataInitialize,
+    },
+};
 
-use crate::{Identity, CreatorToken};
+use holder_hook::{cpi::accounts::InitializeExtraAccountMetaList, program::HolderHook};
+
+use crate::{constants::{MAX_BPS, REGISTRY_PAGE_SIZE}, error::{CreatorTokenError, CustomError, IdentityError}, helpers::{create_creator_mint, create_pda_account, token_index_seed, top_up_rent, validate_token_name, validate_token_symbol, validate_token_uri, CreatorMintAccounts, CreatorMintOptions}, Config, Identity, IdentityStatus, CreatorToken, CreatorVesting, RegistryEntry, RegistryPage};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreateCreatorTokenParams {
+    pub decimals: u8,
+    pub base_price: u64,
+    pub slope: u64,
+    // Token-2022 metadata shown by wallets
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    // Transfer fee on secondary transfers paid to the creator, 0 to disable
+    pub royalty_bps: u16,
+    // Membership pass mode, holders can only buy from and sell back to the curve
+    pub non_transferable: bool,
+    // Base units reserved for the creator and vested from creation, 0 for none
+    pub creator_allocation: u64,
+    pub vesting_cliff_seconds: i64,
+    pub vesting_duration_seconds: i64,
+    // How many of the first distinct buyers get an early holder badge, 0 for none
+    pub early_holder_badges: u16,
+    // Unix timestamp buys open at, anything in the past opens them right away
+    pub trading_starts_at: i64,
+    // Seconds after the start during which buys are capped, 0 for no window
+    pub anti_snipe_seconds: i64,
+    // Base units a single buy and a single wallet can take during the window, 0 for no cap
+    pub anti_snipe_max_buy: u64,
+    pub anti_snipe_max_wallet: u64,
+    // Allowlist root of a presale running until `trading_starts_at`, None for no presale
+    pub presale_merkle_root: Option<[u8; 32]>,
+    // Tax on sells of freshly bought tokens, decaying to 0 over `sell_tax_decay_seconds`. 0 for none
+    pub sell_tax_bps: u16,
+    pub sell_tax_decay_seconds: i64,
+    // Pay the tax to the creator instead of leaving it in the vault
+    pub sell_tax_to_creator: bool,
+}
 
 #[derive(Accounts)]
-#[instruction(decimals: u8)]
 pub struct CreateCreatorToken<'info> {
     #[account(mut)]
     pub creator : Signer<'info>,
 
-    #[account(seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    #[account(
+        mut,
+        seeds=[b"identity", creator.key().as_ref()], 
+        bump=identity_proof.bump,
+        constraint = identity_proof.status == IdentityStatus::Active @ IdentityError::IdentitySuspended
+    )]
     pub identity_proof : Account<'info, Identity>,
 
     #[account(
         init, 
         payer=creator, 
-        seeds=[b"creator_token", identity_proof.key().as_ref()], 
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(identity_proof.token_count)], 
         space=8+CreatorToken::INIT_SPACE, bump
     )]
     pub creator_token : Account<'info, CreatorToken>,
 
+    /// CHECK: Created in the handler by `create_creator_mint`, its extensions depend on the arguments
     #[account(
-        init,
-        payer = creator,
-        mint::decimals = decimals,
-        mint::authority = mint_authority,
-        mint::freeze_authority = mint_authority,
-        seeds = [b"owner", identity_proof.key().as_ref()],
+        mut,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(identity_proof.token_count)],
         bump
     )]
-    pub mint: InterfaceAccount<'info, Mint>,
+    pub mint: UncheckedAccount<'info>,
 
-    // PDA that will act as mint & freeze authority (per-mint flavor).
-    #[account(seeds=[b"mint_authority"], bump)]
+    // Per-mint PDA that acts as mint, freeze and close authority, also the metadata update authority
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump)]
     pub mint_authority: SystemAccount<'info>,
 
-    // Creator's ATA for this mint (where we can send the initial supply)
+    /// CHECK: Creator's ATA for this mint (where vested tokens are claimed to), created in the
+    /// handler once the mint exists, the associated token program checks the address
+    #[account(mut)]
+    pub creator_ata: UncheckedAccount<'info>,
+
+    // Create Vault
+    #[account(seeds=[b"vault", identity_proof.key().as_ref(), &token_index_seed(identity_proof.token_count)], bump)]
+    pub vault : SystemAccount<'info>,
+
+    #[account(mut, seeds=[b"config"], bump=config.bump)]
+    pub config : Account<'info, Config>,
+
+    /// CHECK: Platform group mint Token-2022 creator mints join, checked against the config.
+    /// Only needed for a new Token-2022 mint, like the holder hook accounts below
+    #[account(mut, address = config.group_mint)]
+    pub group_mint : Option<UncheckedAccount<'info>>,
+
+    // Registry page the new token is listed on, opened by the first token that lands on it
     #[account(
-        init,
+        init_if_needed,
         payer = creator,
-        associated_token::mint = mint,
-        associated_token::authority = creator,
-        associated_token::token_program = token_program
+        seeds=[b"registry", &(config.creator_token_count / REGISTRY_PAGE_SIZE).to_le_bytes()],
+        space=8+RegistryPage::INIT_SPACE,
+        bump
     )]
-    pub creator_ata: InterfaceAccount<'info, TokenAccount>,
+    pub registry_page : Account<'info, RegistryPage>,
 
-    // Create Vault
-    #[account(seeds=[b"vault", identity_proof.key().as_ref()], bump)]
-    pub vault : SystemAccount<'info>,
+    /// CHECK: Vesting schedule of the creator allocation, created in the handler. Only needed
+    /// when there is an allocation, ignored otherwise
+    #[account(mut, seeds=[b"vesting", creator_token.key().as_ref()], bump)]
+    pub vesting : Option<UncheckedAccount<'info>>,
+
+    /// CHECK: ATA of `vesting` that holds the allocation, created in the handler once the mint
+    /// exists, the associated token program checks the address
+    #[account(mut)]
+    pub vesting_escrow : Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Validation account of the mint's transfer hook, created by the holder hook program
+    /// for Token-2022 mints
+    #[account(mut)]
+    pub extra_account_meta_list : Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, created by the holder hook program
+    #[account(mut)]
+    pub holder_stats : Option<UncheckedAccount<'info>>,
 
+    pub holder_hook_program : Option<Program<'info, HolderHook>>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program : Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,  
 }
 
-pub fn handler(
-    ctx: Context<CreateCreatorToken>, 
-    decimals : u8, 
-    // inital_supply: u64, 
-    base_price: u64, 
-    slope: u64) -> Result<()> {
-    // Set initial supply
-    // if inital_supply > 0 {
-    //     // mint initial_supply to creator_ata
-
-    //     let cpi_accounts = MintTo {
-    //         authority: ctx.accounts.mint_authority.to_account_info(),
-    //         mint: ctx.accounts.mint.to_account_info(),
-    //         to: ctx.accounts.creator_ata.to_account_info(),
-    //     };
-
-    //     let cpi_program = ctx.accounts.token_program.to_account_info();
-    //     let mint_authority_seeds: &[&[&[u8]]]=  &[&[b"mint_authority", &[ctx.bumps.mint_authority]]];
-
-    //     let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, mint_authority_seeds);
-    //     token_interface::mint_to(cpi_context, inital_supply)?;
-    // }
+pub fn handler(ctx: Context<CreateCreatorToken>, params: CreateCreatorTokenParams) -> Result<()> {
+    let CreateCreatorTokenParams {
+        decimals,
+        base_price,
+        slope,
+        name,
+        symbol,
+        uri,
+        royalty_bps,
+        non_transferable,
+        creator_allocation,
+        vesting_cliff_seconds,
+        vesting_duration_seconds,
+        early_holder_badges,
+        trading_starts_at,
+        anti_snipe_seconds,
+        anti_snipe_max_buy,
+        anti_snipe_max_wallet,
+        presale_merkle_root,
+        sell_tax_bps,
+        sell_tax_decay_seconds,
+        sell_tax_to_creator,
+    } = params;
+
+    require!(base_price > 0 || slope > 0, CreatorTokenError::InvalidCurve);
+    validate_token_name(&name)?;
+    validate_token_symbol(&symbol)?;
+    validate_token_uri(&uri)?;
+    require!(royalty_bps <= ctx.accounts.config.max_royalty_bps, CreatorTokenError::RoyaltyTooHigh);
+    // transfer fees would never be charged on a soulbound token
+    require!(!(non_transferable && royalty_bps > 0), CreatorTokenError::RoyaltyOnNonTransferable);
+    if creator_allocation > 0 {
+        require!(
+            vesting_duration_seconds > 0 && (0..=vesting_duration_seconds).contains(&vesting_cliff_seconds),
+            CreatorTokenError::InvalidVestingSchedule
+        );
+    }
+
+    require!(anti_snipe_seconds >= 0, CreatorTokenError::InvalidLaunchWindow);
+    require!(
+        sell_tax_bps <= MAX_BPS && (sell_tax_bps == 0 || sell_tax_decay_seconds > 0),
+        CreatorTokenError::InvalidSellTax
+    );
+
+    // Legacy SPL Token mints have no extensions, so no royalties, soulbound mode, metadata or holder hook
+    let is_token_2022 = ctx.accounts.token_program.key() == Token2022::id();
+    require!(is_token_2022 || (royalty_bps == 0 && !non_transferable), CreatorTokenError::Token2022Required);
+
+    let identity_proof_key = ctx.accounts.identity_proof.key();
+    let index_seed = token_index_seed(ctx.accounts.identity_proof.token_count);
+    let mint_seeds: &[&[u8]] = &[b"owner", identity_proof_key.as_ref(), &index_seed, &[ctx.bumps.mint]];
+
+    // Mints without a close authority (legacy SPL Token ones and those created before the extension)
+    // outlive close_creator_token. A token re-created at the same index by a re-created identity
+    // takes the leftover over as a plain mint, extensions can't be added to it anymore
+    let reuses_mint = !ctx.accounts.mint.data_is_empty();
+    if reuses_mint {
+        let mint_info = ctx.accounts.mint.to_account_info();
+        require_keys_eq!(*mint_info.owner, ctx.accounts.token_program.key(), CreatorTokenError::LeftoverMintMismatch);
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<MintState>::unpack(&mint_data)?;
+        require!(
+            mint_state.base.supply == 0
+                && mint_state.base.decimals == decimals
+                && mint_state.base.mint_authority == COption::Some(ctx.accounts.mint_authority.key())
+                && mint_state.get_extension_types()?.is_empty()
+                && royalty_bps == 0
+                && !non_transferable,
+            CreatorTokenError::LeftoverMintMismatch
+        );
+    } else {
+        create_creator_mint(
+            &CreatorMintAccounts {
+                payer: ctx.accounts.creator.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                mint_authority: ctx.accounts.mint_authority.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            mint_seeds,
+            &CreatorMintOptions {
+                decimals,
+                transfer_fee_bps: royalty_bps,
+                transfer_hook_program: is_token_2022.then_some(holder_hook::ID),
+                non_transferable,
+                group_member: is_token_2022,
+            },
+        )?;
+    }
+    let has_extensions = is_token_2022 && !reuses_mint;
+
+    let creator_token_key = ctx.accounts.creator_token.key();
+    let mint_authority_seeds: &[&[&[u8]]] = &[&[b"mint_authority", creator_token_key.as_ref(), &[ctx.bumps.mint_authority]]];
+
+    if has_extensions {
+        let (Some(group_mint), Some(extra_account_meta_list), Some(holder_stats), Some(holder_hook_program)) = (
+            ctx.accounts.group_mint.as_ref(),
+            ctx.accounts.extra_account_meta_list.as_ref(),
+            ctx.accounts.holder_stats.as_ref(),
+            ctx.accounts.holder_hook_program.as_ref(),
+        ) else {
+            return err!(CreatorTokenError::Token2022AccountsMissing);
+        };
+
+        // Join the platform group, signed by the mint authority and the config as group authority
+        let accounts = TokenMemberInitialize {
+            program_id: ctx.accounts.token_program.to_account_info(),
+            member: ctx.accounts.mint.to_account_info(),
+            member_mint: ctx.accounts.mint.to_account_info(),
+            member_mint_authority: ctx.accounts.mint_authority.to_account_info(),
+            group: group_mint.to_account_info(),
+            group_update_authority: ctx.accounts.config.to_account_info(),
+        };
+        let group_member_seeds: &[&[&[u8]]] = &[mint_authority_seeds[0], &[b"config", &[ctx.accounts.config.bump]]];
+        token_member_initialize(CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, group_member_seeds))?;
+
+        // Register the mint with the holder hook
+        let accounts = InitializeExtraAccountMetaList {
+            payer: ctx.accounts.creator.to_account_info(),
+            extra_account_meta_list: extra_account_meta_list.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            holder_stats: holder_stats.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        holder_hook::cpi::initialize_extra_account_meta_list(CpiContext::new(holder_hook_program.to_account_info(), accounts))?;
+
+        // Token-2022 grows the mint to store the metadata, fund the extra space first
+        let token_metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey(ctx.accounts.mint_authority.key()),
+            mint: ctx.accounts.mint.key(),
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: uri.clone(),
+            additional_metadata: vec![],
+        };
+        let metadata_len = token_metadata.tlv_size_of()?;
+        top_up_rent(
+            ctx.accounts.creator.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.mint.to_account_info().data_len() + metadata_len,
+        )?;
+
+        let accounts = TokenMetadataInitialize {
+            program_id: ctx.accounts.token_program.to_account_info(),
+            metadata: ctx.accounts.mint.to_account_info(),
+            update_authority: ctx.accounts.mint_authority.to_account_info(),
+            mint_authority: ctx.accounts.mint_authority.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, mint_authority_seeds);
+        token_metadata_initialize(cpi_context, name, symbol, uri)?;
+    }
+
+    // a reused mint may still have the creator's ATA from before
+    let accounts = Create {
+        payer: ctx.accounts.creator.to_account_info(),
+        associated_token: ctx.accounts.creator_ata.to_account_info(),
+        authority: ctx.accounts.creator.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+    };
+    associated_token::create_idempotent(CpiContext::new(ctx.accounts.associated_token_program.to_account_info(), accounts))?;
+
+    let created_at = Clock::get()?.unix_timestamp;
+
+    // Mint the creator allocation into escrow. The curve prices without it so it can never be sold
+    // against the vault, which only holds what buyers paid in
+    if creator_allocation > 0 {
+        let (Some(vesting), Some(vesting_escrow)) = (ctx.accounts.vesting.as_ref(), ctx.accounts.vesting_escrow.as_ref()) else {
+            return err!(CreatorTokenError::VestingAccountsMissing);
+        };
+        let vesting_bump = ctx.bumps.vesting.ok_or(error!(CreatorTokenError::VestingAccountsMissing))?;
+        let space = 8 + CreatorVesting::INIT_SPACE;
+        create_pda_account(
+            ctx.accounts.creator.to_account_info(),
+            vesting.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &crate::ID,
+            &[b"vesting", creator_token_key.as_ref(), &[vesting_bump]],
+        )?;
+
+        let accounts = Create {
+            payer: ctx.accounts.creator.to_account_info(),
+            associated_token: vesting_escrow.to_account_info(),
+            authority: vesting.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        };
+        associated_token::create(CpiContext::new(ctx.accounts.associated_token_program.to_account_info(), accounts))?;
+
+        let accounts = MintTo {
+            authority: ctx.accounts.mint_authority.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: vesting_escrow.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, mint_authority_seeds);
+        token_interface::mint_to(cpi_context, creator_allocation)?;
+
+        let schedule = CreatorVesting {
+            creator_token: creator_token_key,
+            total_amount: creator_allocation,
+            claimed_amount: 0,
+            start_ts: created_at,
+            cliff_ts: created_at.checked_add(vesting_cliff_seconds).ok_or(error!(CustomError::MathOverflow))?,
+            end_ts: created_at.checked_add(vesting_duration_seconds).ok_or(error!(CustomError::MathOverflow))?,
+            bump: vesting_bump,
+        };
+        schedule.try_serialize(&mut &mut vesting.try_borrow_mut_data()?[..])?;
+    }
 
     // May be changed in the future
     // Set creator_token data in PDA
@@ -84,12 +357,57 @@ pub fn handler(
     ctx.accounts.creator_token.vault = ctx.accounts.vault.key();
     ctx.accounts.creator_token.base_price = base_price; // Base price - The starting price per token when supply is at 0
     ctx.accounts.creator_token.slope = slope; // The incremental price increase per additional token minted (rate of increase).
-    // ctx.accounts.creator_token.total_supply = inital_supply;
-    ctx.accounts.creator_token.created_at = Clock::get()?.unix_timestamp;
+    ctx.accounts.creator_token.created_at = created_at;
+    ctx.accounts.creator_token.token_index = ctx.accounts.identity_proof.token_count;
+    ctx.accounts.creator_token.buys_paused = false;
+    ctx.accounts.creator_token.royalty_bps = royalty_bps;
+    ctx.accounts.creator_token.has_holder_hook = has_extensions;
+    ctx.accounts.creator_token.token_program = ctx.accounts.token_program.key();
+    ctx.accounts.creator_token.early_holder_limit = early_holder_badges;
+    ctx.accounts.creator_token.early_holder_count = 0;
+    ctx.accounts.creator_token.sell_order_escrow = 0;
+    ctx.accounts.creator_token.open_limit_orders = 0;
+    let trading_starts_at = trading_starts_at.max(created_at);
+    ctx.accounts.creator_token.trading_starts_at = trading_starts_at;
+    ctx.accounts.creator_token.anti_snipe_ends_at = trading_starts_at.checked_add(anti_snipe_seconds).ok_or(error!(CustomError::MathOverflow))?;
+    ctx.accounts.creator_token.anti_snipe_max_buy = anti_snipe_max_buy;
+    ctx.accounts.creator_token.anti_snipe_max_wallet = anti_snipe_max_wallet;
+    // an all zero root would read as no presale
+    let presale_merkle_root = presale_merkle_root.unwrap_or_default();
+    require!(
+        presale_merkle_root == [0; 32] || trading_starts_at > created_at,
+        CreatorTokenError::InvalidPresale
+    );
+    ctx.accounts.creator_token.presale_merkle_root = presale_merkle_root;
+    ctx.accounts.creator_token.sell_tax_bps = sell_tax_bps;
+    ctx.accounts.creator_token.sell_tax_decay_seconds = sell_tax_decay_seconds;
+    ctx.accounts.creator_token.sell_tax_to_creator = sell_tax_to_creator;
+    ctx.accounts.creator_token.non_transferable = non_transferable;
+    ctx.accounts.creator_token.creator_allocation = creator_allocation;
     ctx.accounts.creator_token.bump = ctx.bumps.creator_token;
     ctx.accounts.creator_token.mint_authority_bump = ctx.bumps.mint_authority;
     ctx.accounts.creator_token.vault_bump = ctx.bumps.vault;
     ctx.accounts.creator_token.mint_bump = ctx.bumps.mint;
 
+    // Next token of this identity gets the next index
+    let identity_proof = &mut ctx.accounts.identity_proof;
+    identity_proof.token_count = identity_proof.token_count.checked_add(1).ok_or(error!(CustomError::MathOverflow))?;
+    identity_proof.active_tokens = identity_proof.active_tokens.checked_add(1).ok_or(error!(CustomError::MathOverflow))?;
+
+    // List the token in the registry
+    let registry_page = &mut ctx.accounts.registry_page;
+    if registry_page.entries.is_empty() {
+        registry_page.page_index = ctx.accounts.config.creator_token_count / REGISTRY_PAGE_SIZE;
+        registry_page.bump = ctx.bumps.registry_page;
+    }
+    registry_page.entries.push(RegistryEntry {
+        mint: ctx.accounts.mint.key(),
+        identity: ctx.accounts.identity_proof.key(),
+        created_at: ctx.accounts.creator_token.created_at,
+    });
+
+    let config = &mut ctx.accounts.config;
+    config.creator_token_count = config.creator_token_count.checked_add(1).ok_or(error!(CustomError::MathOverflow))?;
+
     Ok(())
 }
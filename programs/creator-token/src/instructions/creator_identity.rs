@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::{error::IdentityError, Identity};
+use crate::{error::IdentityError, Identity, IdentityStatus, TokenCounter};
 
 #[derive(Accounts)]
 pub struct CreatorIdentity<'info> {
@@ -10,6 +10,10 @@ pub struct CreatorIdentity<'info> {
     #[account(init, payer=creator, seeds=[b"identity", creator.key().as_ref()], space=8+Identity::INIT_SPACE, bump)]
     pub identity_proof : Account<'info, Identity>,
 
+    /// CHECK: only exists if the creator closed an identity before, read in the handler
+    #[account(seeds=[b"token_counter", creator.key().as_ref()], bump)]
+    pub token_counter : UncheckedAccount<'info>,
+
     pub system_program : Program<'info, System>
 }
 
@@ -21,6 +25,17 @@ pub fn handler(ctx: Context<CreatorIdentity>, user_name : String, proof_url : St
     ctx.accounts.identity_proof.creator_name = user_name;
     ctx.accounts.identity_proof.proof_url = proof_url;
     ctx.accounts.identity_proof.bump = ctx.bumps.identity_proof;
+    ctx.accounts.identity_proof.status = IdentityStatus::Active;
+    ctx.accounts.identity_proof.suspension_reason = 0;
+    // Continue where a closed identity stopped so its token PDAs are never reused
+    ctx.accounts.identity_proof.token_count = if ctx.accounts.token_counter.owner == &crate::ID {
+        let data = ctx.accounts.token_counter.try_borrow_data()?;
+        TokenCounter::try_deserialize(&mut &data[..])?.token_count
+    } else {
+        0
+    };
+    ctx.accounts.identity_proof.active_tokens = 0;
+    ctx.accounts.identity_proof.delegates = Vec::new();
 
     Ok(())
 }
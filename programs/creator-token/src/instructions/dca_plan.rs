@@ -0,0 +1,319 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use holder_hook::program::HolderHook;
+
+use crate::{
+    constants::{DCA_EXECUTOR_TIP, MAX_BPS},
+    error::{CreatorTokenError, CustomError, IdentityError},
+    events::DcaExecuted,
+    helpers::{
+        execute_curve_buy, get_marginal_price, get_tokens_for_lamports, token_index_seed, BuyFunding, CurveBuyAccounts,
+        LaunchAccess,
+    },
+    Config, CreatorToken, DcaPlan, Identity, IdentityStatus,
+};
+
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct CreateDcaPlan<'info> {
+    #[account(mut)]
+    pub owner : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Box<Account<'info, Identity>>,
+
+    #[account(
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.bump
+    )]
+    pub creator_token : Box<Account<'info, CreatorToken>>,
+
+    #[account(
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // created now so executions have somewhere to mint to
+    #[account(
+        init_if_needed,
+        payer=owner,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+        constraint = !owner_ata.is_frozen() @ CreatorTokenError::TokenAccountFrozen
+    )]
+    pub owner_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer=owner,
+        seeds=[b"dca_plan", creator_token.key().as_ref(), owner.key().as_ref()],
+        space=8+DcaPlan::INIT_SPACE,
+        bump
+    )]
+    pub plan : Box<Account<'info, DcaPlan>>,
+
+    #[account(address = creator_token.token_program)]
+    pub token_program : Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program : Program<'info, System>,
+}
+
+// The plan's creator token may be closed already, so it's derived from the stored key
+#[derive(Accounts)]
+pub struct ManageDcaPlan<'info> {
+    #[account(mut)]
+    pub owner : Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds=[b"dca_plan", plan.creator_token.as_ref(), owner.key().as_ref()],
+        bump=plan.bump
+    )]
+    pub plan : Account<'info, DcaPlan>,
+
+    pub system_program : Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDcaPlan<'info> {
+    #[account(mut)]
+    pub owner : Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds=[b"dca_plan", plan.creator_token.as_ref(), owner.key().as_ref()],
+        bump=plan.bump
+    )]
+    pub plan : Account<'info, DcaPlan>,
+}
+
+// Permissionless, anyone can run a plan's next buy once it is due and earn the tip
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct ExecuteDca<'info> {
+    #[account(mut)]
+    pub executor : Signer<'info>,
+
+    #[account(address = plan.owner)]
+    pub owner : SystemAccount<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(
+        seeds=[b"identity", creator.key().as_ref()],
+        bump=identity_proof.bump,
+        constraint = identity_proof.status == IdentityStatus::Active @ IdentityError::IdentitySuspended
+    )]
+    pub identity_proof : Box<Account<'info, Identity>>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.bump,
+        constraint = !creator_token.buys_paused @ CreatorTokenError::BuysPaused
+    )]
+    pub creator_token : Box<Account<'info, CreatorToken>>,
+
+    #[account(
+        mut,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    #[account(mut, seeds=[b"vault", identity_proof.key().as_ref(), &token_index_seed(token_index)], bump=creator_token.vault_bump)]
+    pub vault : SystemAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+        constraint = !owner_ata.is_frozen() @ CreatorTokenError::TokenAccountFrozen
+    )]
+    pub owner_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds=[b"dca_plan", creator_token.key().as_ref(), owner.key().as_ref()],
+        bump=plan.bump
+    )]
+    pub plan : Box<Account<'info, DcaPlan>>,
+
+    #[account(seeds=[b"config"], bump=config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut, seeds=[b"fee_vault"], bump=config.fee_vault_bump)]
+    pub fee_vault: SystemAccount<'info>,
+
+    #[account(address = creator_token.token_program)]
+    pub token_program : Interface<'info, TokenInterface>,
+    pub system_program : Program<'info, System>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the owner, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+}
+
+fn deposit<'info>(owner: &Signer<'info>, plan: &mut Account<'info, DcaPlan>, system_program: AccountInfo<'info>, amount: u64) -> Result<()> {
+    let accounts = Transfer {
+        from: owner.to_account_info(),
+        to: plan.to_account_info(),
+    };
+    transfer(CpiContext::new(system_program, accounts), amount)?;
+    plan.balance = plan.balance.checked_add(amount).ok_or(error!(CustomError::MathOverflow))?;
+    Ok(())
+}
+
+pub fn create(
+    ctx: Context<CreateDcaPlan>,
+    _token_index: u16,
+    amount_per_interval: u64,
+    interval_seconds: i64,
+    max_price: u64,
+    initial_deposit: u64,
+) -> Result<()> {
+    require!(amount_per_interval > 0 && interval_seconds > 0 && max_price > 0, CreatorTokenError::InvalidDcaPlan);
+    // executions could never buy a sell taxed token, refuse the plan instead of taking the deposit
+    ctx.accounts.creator_token.check_untaxed()?;
+
+    let plan = &mut ctx.accounts.plan;
+    plan.owner = ctx.accounts.owner.key();
+    plan.creator_token = ctx.accounts.creator_token.key();
+    plan.balance = 0;
+    plan.amount_per_interval = amount_per_interval;
+    plan.interval_seconds = interval_seconds;
+    plan.max_price = max_price;
+    // the first buy is due right away
+    plan.next_execution_at = Clock::get()?.unix_timestamp;
+    plan.paused = false;
+    plan.total_spent = 0;
+    plan.total_bought = 0;
+    plan.bump = ctx.bumps.plan;
+
+    deposit(&ctx.accounts.owner, &mut ctx.accounts.plan, ctx.accounts.system_program.to_account_info(), initial_deposit)
+}
+
+pub fn top_up(ctx: Context<ManageDcaPlan>, amount: u64) -> Result<()> {
+    deposit(&ctx.accounts.owner, &mut ctx.accounts.plan, ctx.accounts.system_program.to_account_info(), amount)
+}
+
+pub fn set_paused(ctx: Context<ManageDcaPlan>, paused: bool) -> Result<()> {
+    ctx.accounts.plan.paused = paused;
+    Ok(())
+}
+
+// Closing the plan hands the remaining balance and the rent back to the owner
+pub fn close(_ctx: Context<CloseDcaPlan>) -> Result<()> {
+    Ok(())
+}
+
+pub fn execute(ctx: Context<ExecuteDca>, _token_index: u16) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let plan = &ctx.accounts.plan;
+    require!(!plan.paused, CreatorTokenError::DcaPlanPaused);
+    require!(now >= plan.next_execution_at, CreatorTokenError::DcaNotDue);
+
+    let curve_supply = ctx.accounts.creator_token.curve_supply(ctx.accounts.mint.supply)?;
+    let base_price = ctx.accounts.creator_token.base_price;
+    let slope = ctx.accounts.creator_token.slope;
+    let decimals = ctx.accounts.mint.decimals;
+    let marginal_price = get_marginal_price(curve_supply, base_price, slope, decimals)?;
+    require!(marginal_price <= plan.max_price, CreatorTokenError::DcaPriceAboveMax);
+
+    // the last execution spends whatever is left after the tip
+    require!(plan.balance > DCA_EXECUTOR_TIP, CreatorTokenError::DcaInsufficientBalance);
+    let spend = plan.amount_per_interval.min(plan.balance - DCA_EXECUTOR_TIP);
+
+    // leave room for the trading fee on top of the curve cost
+    let fee_bps = ctx.accounts.config.trade_fee_bps as u128;
+    let curve_budget = u64::try_from(spend as u128 * MAX_BPS as u128 / (MAX_BPS as u128 + fee_bps))
+        .map_err(|_| error!(CustomError::MathOverflow))?;
+    let tokens = get_tokens_for_lamports(curve_budget, curve_supply, base_price, slope, decimals)?;
+    require!(tokens > 0, CreatorTokenError::DcaInsufficientBalance);
+
+    // the plan is owned by this program and escrows the lamports of the buy
+    let accounts = &mut *ctx.accounts;
+    let purchase = execute_curve_buy(
+        CurveBuyAccounts {
+            payer: accounts.executor.to_account_info(),
+            funding: BuyFunding::Escrow(accounts.plan.to_account_info()),
+            recipient: accounts.owner.to_account_info(),
+            recipient_ata: accounts.owner_ata.to_account_info(),
+            creator_token: &mut accounts.creator_token,
+            mint: &accounts.mint,
+            mint_authority: accounts.mint_authority.to_account_info(),
+            vault: accounts.vault.to_account_info(),
+            config: &accounts.config,
+            fee_vault: accounts.fee_vault.to_account_info(),
+            referrer: None,
+            token_program: accounts.token_program.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            holder_hook_program: accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+            hook_authority: accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+            holder_record: accounts.holder_record.as_ref().map(|account| account.to_account_info()),
+            holder_stats: accounts.holder_stats.as_ref().map(|account| account.to_account_info()),
+            hook_authority_bump: ctx.bumps.hook_authority,
+            launch_buys: None,
+            launch_buys_bump: None,
+            position: None,
+            position_bump: None,
+            badge: None,
+        },
+        tokens,
+        None,
+        LaunchAccess::OpenOnly,
+    )?;
+    let spent = purchase.total_price + purchase.fee.total();
+
+    accounts.plan.sub_lamports(DCA_EXECUTOR_TIP)?;
+    accounts.executor.add_lamports(DCA_EXECUTOR_TIP)?;
+
+    let plan = &mut accounts.plan;
+    plan.balance = spent
+        .checked_add(DCA_EXECUTOR_TIP)
+        .and_then(|total| plan.balance.checked_sub(total))
+        .ok_or(error!(CustomError::MathOverflow))?;
+    plan.total_spent = plan.total_spent.checked_add(spent).ok_or(error!(CustomError::MathOverflow))?;
+    plan.total_bought = plan.total_bought.checked_add(tokens).ok_or(error!(CustomError::MathOverflow))?;
+    // missed intervals aren't caught up, the next buy is one interval from now
+    plan.next_execution_at = now.checked_add(plan.interval_seconds).ok_or(error!(CustomError::MathOverflow))?;
+
+    emit!(DcaExecuted {
+        plan: ctx.accounts.plan.key(),
+        owner: ctx.accounts.owner.key(),
+        executor: ctx.accounts.executor.key(),
+        mint: ctx.accounts.mint.key(),
+        tokens,
+        lamports: purchase.total_price,
+        remaining_balance: ctx.accounts.plan.balance,
+        timestamp: now,
+    });
+    Ok(())
+}
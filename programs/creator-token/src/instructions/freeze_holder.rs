@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, FreezeAccount, Mint, ThawAccount, TokenAccount, TokenInterface};
+
+use crate::{
+    error::AdminError,
+    events::{HolderFrozen, HolderThawed},
+    helpers::token_index_seed,
+    Config, CreatorToken, Identity,
+};
+
+// Compliance freezes (court orders, stolen funds), the program's mint authority
+// PDA is also the freeze authority of every creator mint
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct FreezeHolder<'info> {
+    pub admin : Signer<'info>,
+
+    #[account(seeds=[b"config"], bump=config.bump, has_one = admin @ AdminError::Unauthorized)]
+    pub config : Account<'info, Config>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Account<'info, Identity>,
+
+    #[account(
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
+        bump=creator_token.bump
+    )]
+    pub creator_token : Account<'info, CreatorToken>,
+
+    #[account(
+        mint::freeze_authority = mint_authority,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    #[account(mut, token::mint = mint, token::token_program = token_program)]
+    pub holder_account : InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = creator_token.token_program)]
+    pub token_program : Interface<'info, TokenInterface>,
+}
+
+pub fn freeze(ctx: Context<FreezeHolder>, _token_index: u16, reason: u16) -> Result<()> {
+    let accounts = FreezeAccount {
+        account: ctx.accounts.holder_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    let creator_token_key = ctx.accounts.creator_token.key();
+    let mint_authority_seeds: &[&[&[u8]]] = &[&[b"mint_authority", creator_token_key.as_ref(), &[ctx.accounts.creator_token.mint_authority_bump]]];
+    let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, mint_authority_seeds);
+    token_interface::freeze_account(cpi_context)?;
+
+    emit!(HolderFrozen {
+        mint: ctx.accounts.mint.key(),
+        holder_account: ctx.accounts.holder_account.key(),
+        owner: ctx.accounts.holder_account.owner,
+        admin: ctx.accounts.admin.key(),
+        reason,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn thaw(ctx: Context<FreezeHolder>, _token_index: u16) -> Result<()> {
+    let accounts = ThawAccount {
+        account: ctx.accounts.holder_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    let creator_token_key = ctx.accounts.creator_token.key();
+    let mint_authority_seeds: &[&[&[u8]]] = &[&[b"mint_authority", creator_token_key.as_ref(), &[ctx.accounts.creator_token.mint_authority_bump]]];
+    let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, mint_authority_seeds);
+    token_interface::thaw_account(cpi_context)?;
+
+    emit!(HolderThawed {
+        mint: ctx.accounts.mint.key(),
+        holder_account: ctx.accounts.holder_account.key(),
+        owner: ctx.accounts.holder_account.owner,
+        admin: ctx.accounts.admin.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, Token2022, TokenAccount, TokenInterface},
+};
+
+use holder_hook::program::HolderHook;
+
+use crate::{
+    error::{CreatorTokenError, IdentityError},
+    events::CreatorTokenGifted,
+    helpers::{check_deadline, execute_curve_buy, token_index_seed, BadgeInputs, BuyFunding, CurveBuyAccounts, LaunchAccess},
+    Config, CreatorToken, Identity, IdentityStatus, LaunchBuys, PresaleAllocation, Referrer,
+};
+
+// Same as BuyToken, except the tokens go to `recipient` instead of the signer
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct GiftToken<'info> {
+    #[account(mut)]
+    pub payer : Signer<'info>,
+
+    /// CHECK: Wallet receiving the tokens, only used as the owner of its ATA, launch buys and badge
+    pub recipient : AccountInfo<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(
+        seeds=[b"identity", creator.key().as_ref()],
+        bump=identity_proof.bump,
+        constraint = identity_proof.status == IdentityStatus::Active @ IdentityError::IdentitySuspended
+    )]
+    pub identity_proof : Box<Account<'info, Identity>>,
+
+    #[account(mut, seeds=[b"vault", identity_proof.key().as_ref(), &token_index_seed(token_index)], bump=creator_token.vault_bump)]
+    pub vault : SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.bump,
+        constraint = !creator_token.buys_paused @ CreatorTokenError::BuysPaused
+    )]
+    pub creator_token : Box<Account<'info, CreatorToken>>,
+
+    #[account(
+        mut,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer=payer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program,
+        constraint = !recipient_ata.is_frozen() @ CreatorTokenError::TokenAccountFrozen
+    )]
+    pub recipient_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program : Program<'info, System>,
+    #[account(address = creator_token.token_program)]
+    pub token_program : Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the recipient, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Recipient's early holder badge mint, created in the handler on a qualifying first buy
+    #[account(mut, seeds=[b"badge", creator_token.key().as_ref(), recipient.key().as_ref()], bump)]
+    pub badge_mint: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Recipient's ATA for the badge, the associated token program checks the address
+    #[account(mut)]
+    pub badge_ata: Option<UncheckedAccount<'info>>,
+
+    // badges are Token-2022 NFTs whichever program the creator token uses
+    pub badge_token_program: Option<Program<'info, Token2022>>,
+
+    #[account(seeds=[b"config"], bump=config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut, seeds=[b"fee_vault"], bump=config.fee_vault_bump)]
+    pub fee_vault: SystemAccount<'info>,
+
+    // affiliate that brought the payer in, takes a share of the trading fee
+    #[account(
+        mut,
+        seeds=[b"referrer", referrer.authority.as_ref()],
+        bump=referrer.bump,
+        constraint = referrer.authority != payer.key() @ CreatorTokenError::SelfReferral
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+
+    // the presale and anti-snipe limits count against the recipient
+    #[account(
+        init_if_needed,
+        payer=payer,
+        seeds=[b"launch_buys", creator_token.key().as_ref(), recipient.key().as_ref()],
+        space=8+LaunchBuys::INIT_SPACE,
+        bump
+    )]
+    pub launch_buys: Option<Box<Account<'info, LaunchBuys>>>,
+}
+
+// The recipient is treated as the buyer: its presale cap, launch limits and badge. Gifted tokens stay out
+// of its sell tax position, so a gift can't move the age of tokens it bought and pays the full tax
+pub fn handler(
+    ctx: Context<GiftToken>,
+    _token_index: u16,
+    tokens_to_buy: u64,
+    expires_at: Option<i64>,
+    presale: Option<PresaleAllocation>,
+) -> Result<()> {
+    check_deadline(expires_at)?;
+
+    let accounts = &mut *ctx.accounts;
+    let purchase = execute_curve_buy(
+        CurveBuyAccounts {
+            payer: accounts.payer.to_account_info(),
+            funding: BuyFunding::Wallet(accounts.payer.to_account_info()),
+            recipient: accounts.recipient.to_account_info(),
+            recipient_ata: accounts.recipient_ata.to_account_info(),
+            creator_token: &mut accounts.creator_token,
+            mint: &accounts.mint,
+            mint_authority: accounts.mint_authority.to_account_info(),
+            vault: accounts.vault.to_account_info(),
+            config: &accounts.config,
+            fee_vault: accounts.fee_vault.to_account_info(),
+            referrer: accounts.referrer.as_mut(),
+            token_program: accounts.token_program.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            holder_hook_program: accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+            hook_authority: accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+            holder_record: accounts.holder_record.as_ref().map(|account| account.to_account_info()),
+            holder_stats: accounts.holder_stats.as_ref().map(|account| account.to_account_info()),
+            hook_authority_bump: ctx.bumps.hook_authority,
+            launch_buys: accounts.launch_buys.as_deref_mut(),
+            launch_buys_bump: ctx.bumps.launch_buys,
+            position: None,
+            position_bump: None,
+            badge: Some(BadgeInputs {
+                badge_mint: accounts.badge_mint.as_ref().map(|account| account.to_account_info()),
+                badge_ata: accounts.badge_ata.as_ref().map(|account| account.to_account_info()),
+                badge_token_program: accounts.badge_token_program.as_ref().map(|program| program.to_account_info()),
+                badge_mint_bump: ctx.bumps.badge_mint,
+                associated_token_program: accounts.associated_token_program.to_account_info(),
+            }),
+        },
+        tokens_to_buy,
+        None,
+        LaunchAccess::Tracked(presale),
+    )?;
+
+    emit!(CreatorTokenGifted {
+        mint: accounts.mint.key(),
+        payer: accounts.payer.key(),
+        recipient: accounts.recipient.key(),
+        tokens: tokens_to_buy,
+        lamports: purchase.total_price,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
@@ -1,9 +1,98 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::token_interface::{
+    group_pointer_initialize, initialize_mint2,
+    spl_token_2022::{extension::ExtensionType, state::Mint as MintState},
+    token_group_initialize, GroupPointerInitialize, InitializeMint2, Token2022, TokenGroupInitialize,
+};
+
+use crate::{constants::MAX_BPS, error::AdminError, helpers::create_pda_account, program::CreatorToken, Config};
 
 #[derive(Accounts)]
-pub struct Initialize {}
+pub struct Initialize<'info> {
+    // only the upgrade authority can set up the platform, otherwise anyone could claim the admin role first
+    #[account(mut)]
+    pub admin : Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program : Program<'info, CreatorToken>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AdminError::Unauthorized)]
+    pub program_data : Account<'info, ProgramData>,
+
+    #[account(init, payer=admin, seeds=[b"config"], space=8+Config::INIT_SPACE, bump)]
+    pub config : Account<'info, Config>,
+
+    /// CHECK: Platform group mint, created in the handler with the group extensions
+    #[account(mut, seeds=[b"platform_group"], bump)]
+    pub group_mint : UncheckedAccount<'info>,
+
+    // platform share of the trading fees
+    #[account(mut, seeds=[b"fee_vault"], bump)]
+    pub fee_vault : SystemAccount<'info>,
+
+    pub token_program : Program<'info, Token2022>,
+    pub system_program : Program<'info, System>
+}
+
+pub fn handler(ctx: Context<Initialize>, max_royalty_bps: u16) -> Result<()> {
+    require!(max_royalty_bps <= MAX_BPS, AdminError::InvalidBps);
 
-pub fn handler(ctx: Context<Initialize>) -> Result<()> {
     msg!("Greetings from: {:?}", ctx.program_id);
+
+    ctx.accounts.config.admin = ctx.accounts.admin.key();
+    ctx.accounts.config.creator_token_count = 0;
+    ctx.accounts.config.max_royalty_bps = max_royalty_bps;
+    ctx.accounts.config.group_mint = ctx.accounts.group_mint.key();
+    ctx.accounts.config.group_mint_bump = ctx.bumps.group_mint;
+    ctx.accounts.config.trade_fee_bps = 0;
+    ctx.accounts.config.referral_share_bps = 0;
+    ctx.accounts.config.fee_vault_bump = ctx.bumps.fee_vault;
+    ctx.accounts.config.bump = ctx.bumps.config;
+
+    // fund the fee vault up front so fees smaller than its rent exemption can land in it
+    let accounts = Transfer {
+        from: ctx.accounts.admin.to_account_info(),
+        to: ctx.accounts.fee_vault.to_account_info(),
+    };
+    transfer(CpiContext::new(ctx.accounts.system_program.to_account_info(), accounts), Rent::get()?.minimum_balance(0))?;
+
+    // Platform group mint, the config is its mint authority and group update authority so only
+    // this program can add members. Token-2022 grows it to fit the group, that rent is paid up front.
+    let space = ExtensionType::try_calculate_account_len::<MintState>(&[ExtensionType::GroupPointer])?;
+    let funded_space = ExtensionType::try_calculate_account_len::<MintState>(&[ExtensionType::GroupPointer, ExtensionType::TokenGroup])?;
+    create_pda_account(
+        ctx.accounts.admin.to_account_info(),
+        ctx.accounts.group_mint.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+        Rent::get()?.minimum_balance(funded_space),
+        space as u64,
+        &Token2022::id(),
+        &[b"platform_group", &[ctx.bumps.group_mint]],
+    )?;
+
+    let config_key = ctx.accounts.config.key();
+    let accounts = GroupPointerInitialize {
+        token_program_id: ctx.accounts.token_program.to_account_info(),
+        mint: ctx.accounts.group_mint.to_account_info(),
+    };
+    group_pointer_initialize(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), accounts),
+        Some(config_key),
+        Some(ctx.accounts.group_mint.key()),
+    )?;
+
+    let accounts = InitializeMint2 { mint: ctx.accounts.group_mint.to_account_info() };
+    initialize_mint2(CpiContext::new(ctx.accounts.token_program.to_account_info(), accounts), 0, &config_key, None)?;
+
+    let accounts = TokenGroupInitialize {
+        program_id: ctx.accounts.token_program.to_account_info(),
+        group: ctx.accounts.group_mint.to_account_info(),
+        mint: ctx.accounts.group_mint.to_account_info(),
+        mint_authority: ctx.accounts.config.to_account_info(),
+    };
+    let config_seeds: &[&[&[u8]]] = &[&[b"config", &[ctx.bumps.config]]];
+    let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, config_seeds);
+    token_group_initialize(cpi_context, Some(config_key), u64::MAX)?;
+
     Ok(())
 }
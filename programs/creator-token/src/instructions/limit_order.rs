@@ -0,0 +1,435 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{self, burn, Burn, Mint, MintTo, TokenAccount, TokenInterface},
+};
+
+use holder_hook::program::HolderHook;
+
+use crate::{
+    constants::LIMIT_ORDER_KEEPER_REWARD,
+    error::{CreatorTokenError, CustomError, IdentityError},
+    events::LimitOrderFilled,
+    helpers::{
+        execute_curve_buy, get_marginal_price, get_selling_return, get_trade_fee, pay_trade_fee, record_holder_burn,
+        record_holder_mint, token_index_seed, BuyFunding, CurveBuyAccounts, HolderHookAccounts, LaunchAccess,
+    },
+    Config, CreatorToken, Identity, IdentityStatus, LimitOrder, LimitOrderSide,
+};
+
+#[derive(Accounts)]
+#[instruction(token_index: u16, order_id: u64)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(mut)]
+    pub owner : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Box<Account<'info, Identity>>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.bump
+    )]
+    pub creator_token : Box<Account<'info, CreatorToken>>,
+
+    #[account(
+        mut,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // created for buy orders so the fill has somewhere to mint to
+    #[account(
+        init_if_needed,
+        payer=owner,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+        constraint = !owner_ata.is_frozen() @ CreatorTokenError::TokenAccountFrozen
+    )]
+    pub owner_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer=owner,
+        seeds=[b"limit_order", creator_token.key().as_ref(), owner.key().as_ref(), &order_id.to_le_bytes()],
+        space=8+LimitOrder::INIT_SPACE,
+        bump
+    )]
+    pub order : Box<Account<'info, LimitOrder>>,
+
+    #[account(address = creator_token.token_program)]
+    pub token_program : Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program : Program<'info, System>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the owner, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+}
+
+// Permissionless, anyone can fill an order once the curve reaches its limit price
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct FillLimitOrder<'info> {
+    #[account(mut)]
+    pub keeper : Signer<'info>,
+
+    #[account(mut, address = order.owner)]
+    pub owner : SystemAccount<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Box<Account<'info, Identity>>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.bump
+    )]
+    pub creator_token : Box<Account<'info, CreatorToken>>,
+
+    #[account(
+        mut,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    #[account(mut, seeds=[b"vault", identity_proof.key().as_ref(), &token_index_seed(token_index)], bump=creator_token.vault_bump)]
+    pub vault : SystemAccount<'info>,
+
+    // buy orders mint to it, sell orders of a frozen holder don't fill either
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+        constraint = !owner_ata.is_frozen() @ CreatorTokenError::TokenAccountFrozen
+    )]
+    pub owner_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds=[b"limit_order", creator_token.key().as_ref(), owner.key().as_ref(), &order.order_id.to_le_bytes()],
+        bump=order.bump
+    )]
+    pub order : Box<Account<'info, LimitOrder>>,
+
+    #[account(seeds=[b"config"], bump=config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut, seeds=[b"fee_vault"], bump=config.fee_vault_bump)]
+    pub fee_vault: SystemAccount<'info>,
+
+    #[account(address = creator_token.token_program)]
+    pub token_program : Interface<'info, TokenInterface>,
+    pub system_program : Program<'info, System>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the owner, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct CancelLimitOrder<'info> {
+    #[account(mut)]
+    pub owner : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Box<Account<'info, Identity>>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.bump
+    )]
+    pub creator_token : Box<Account<'info, CreatorToken>>,
+
+    #[account(
+        mut,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    // sell orders only, the escrowed tokens are minted back to it
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds=[b"limit_order", creator_token.key().as_ref(), owner.key().as_ref(), &order.order_id.to_le_bytes()],
+        bump=order.bump
+    )]
+    pub order : Box<Account<'info, LimitOrder>>,
+
+    #[account(address = creator_token.token_program)]
+    pub token_program : Interface<'info, TokenInterface>,
+    pub system_program : Program<'info, System>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the owner, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn place(
+    ctx: Context<PlaceLimitOrder>,
+    _token_index: u16,
+    order_id: u64,
+    side: LimitOrderSide,
+    amount: u64,
+    limit_price: u64,
+    max_cost: u64,
+) -> Result<()> {
+    require!(amount > 0 && limit_price > 0, CreatorTokenError::InvalidLimitOrder);
+    ctx.accounts.creator_token.check_untaxed()?;
+    require!(side == LimitOrderSide::Sell || max_cost > 0, CreatorTokenError::InvalidLimitOrder);
+
+    let order = &mut ctx.accounts.order;
+    order.owner = ctx.accounts.owner.key();
+    order.creator_token = ctx.accounts.creator_token.key();
+    order.order_id = order_id;
+    order.side = side;
+    order.amount = amount;
+    order.limit_price = limit_price;
+    order.max_cost = if side == LimitOrderSide::Buy { max_cost } else { 0 };
+    order.created_at = Clock::get()?.unix_timestamp;
+    order.bump = ctx.bumps.order;
+
+    // the order account holds the keeper reward, and the budget of a buy
+    let escrow = LIMIT_ORDER_KEEPER_REWARD.checked_add(order.max_cost).ok_or(error!(CustomError::MathOverflow))?;
+    let accounts = Transfer {
+        from: ctx.accounts.owner.to_account_info(),
+        to: ctx.accounts.order.to_account_info(),
+    };
+    transfer(CpiContext::new(ctx.accounts.system_program.to_account_info(), accounts), escrow)?;
+
+    // sold tokens are burned now and stay in the curve supply until the fill
+    if side == LimitOrderSide::Sell {
+        let accounts = Burn {
+            authority: ctx.accounts.owner.to_account_info(),
+            from: ctx.accounts.owner_ata.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+        };
+        burn(CpiContext::new(ctx.accounts.token_program.to_account_info(), accounts), amount)?;
+
+        if ctx.accounts.creator_token.has_holder_hook {
+            let hook = HolderHookAccounts::new(
+                ctx.accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+                ctx.accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.holder_record.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.holder_stats.as_ref().map(|account| account.to_account_info()),
+                ctx.bumps.hook_authority,
+            )?;
+            ctx.accounts.creator_token.holder_count =
+                record_holder_burn(&hook, ctx.accounts.mint.to_account_info(), ctx.accounts.owner.to_account_info(), amount)?;
+        }
+
+        let creator_token = &mut ctx.accounts.creator_token;
+        creator_token.sell_order_escrow = creator_token.sell_order_escrow.checked_add(amount).ok_or(error!(CustomError::MathOverflow))?;
+    }
+
+    let creator_token = &mut ctx.accounts.creator_token;
+    creator_token.open_limit_orders = creator_token.open_limit_orders.checked_add(1).ok_or(error!(CustomError::MathOverflow))?;
+    Ok(())
+}
+
+pub fn fill(ctx: Context<FillLimitOrder>, token_index: u16) -> Result<()> {
+    let curve_supply = ctx.accounts.creator_token.curve_supply(ctx.accounts.mint.supply)?;
+    let base_price = ctx.accounts.creator_token.base_price;
+    let slope = ctx.accounts.creator_token.slope;
+    let decimals = ctx.accounts.mint.decimals;
+    let marginal_price = get_marginal_price(curve_supply, base_price, slope, decimals)?;
+    let side = ctx.accounts.order.side;
+    let amount = ctx.accounts.order.amount;
+
+    let lamports = match side {
+        LimitOrderSide::Buy => {
+            require!(ctx.accounts.identity_proof.status == IdentityStatus::Active, IdentityError::IdentitySuspended);
+            require!(!ctx.accounts.creator_token.buys_paused, CreatorTokenError::BuysPaused);
+            require!(marginal_price <= ctx.accounts.order.limit_price, CreatorTokenError::LimitPriceNotReached);
+            let max_cost = ctx.accounts.order.max_cost;
+
+            let accounts = &mut *ctx.accounts;
+            // the order is owned by this program and escrows the lamports of the buy
+            let purchase = execute_curve_buy(
+                CurveBuyAccounts {
+                    payer: accounts.keeper.to_account_info(),
+                    funding: BuyFunding::Escrow(accounts.order.to_account_info()),
+                    recipient: accounts.owner.to_account_info(),
+                    recipient_ata: accounts.owner_ata.to_account_info(),
+                    creator_token: &mut accounts.creator_token,
+                    mint: &accounts.mint,
+                    mint_authority: accounts.mint_authority.to_account_info(),
+                    vault: accounts.vault.to_account_info(),
+                    config: &accounts.config,
+                    fee_vault: accounts.fee_vault.to_account_info(),
+                    referrer: None,
+                    token_program: accounts.token_program.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    holder_hook_program: accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+                    hook_authority: accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+                    holder_record: accounts.holder_record.as_ref().map(|account| account.to_account_info()),
+                    holder_stats: accounts.holder_stats.as_ref().map(|account| account.to_account_info()),
+                    hook_authority_bump: ctx.bumps.hook_authority,
+                    launch_buys: None,
+                    launch_buys_bump: None,
+                    position: None,
+                    position_bump: None,
+                    badge: None,
+                },
+                amount,
+                Some(max_cost),
+                LaunchAccess::OpenOnly,
+            )?;
+            purchase.total_price
+        }
+        LimitOrderSide::Sell => {
+            require!(marginal_price >= ctx.accounts.order.limit_price, CreatorTokenError::LimitPriceNotReached);
+            // sell orders of allocation tokens can't fill past what buyers paid for
+            require!(amount <= curve_supply, CreatorTokenError::InsufficientCurveSupply);
+
+            let token_return = get_selling_return(amount, curve_supply, base_price, slope, decimals)?;
+            let fee = get_trade_fee(&ctx.accounts.config, token_return, false)?;
+            let creator_token = &mut ctx.accounts.creator_token;
+            creator_token.sell_order_escrow = creator_token.sell_order_escrow.checked_sub(amount).ok_or(error!(CustomError::MathOverflow))?;
+
+            let identity_proof_key = ctx.accounts.identity_proof.key();
+            let index_seed = token_index_seed(token_index);
+            let vault_seeds: &[&[&[u8]]] = &[&[b"vault", identity_proof_key.as_ref(), &index_seed, &[ctx.accounts.creator_token.vault_bump]]];
+            pay_trade_fee(
+                &fee,
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.fee_vault.to_account_info(),
+                None,
+                ctx.accounts.system_program.to_account_info(),
+                vault_seeds,
+            )?;
+            let accounts = Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.owner.to_account_info(),
+            };
+            transfer(CpiContext::new_with_signer(ctx.accounts.system_program.to_account_info(), accounts, vault_seeds), token_return - fee.total())?;
+            token_return
+        }
+    };
+
+    // keeper reward, the rest of the order's lamports go back to the owner when it closes
+    ctx.accounts.order.sub_lamports(LIMIT_ORDER_KEEPER_REWARD)?;
+    ctx.accounts.keeper.add_lamports(LIMIT_ORDER_KEEPER_REWARD)?;
+
+    let creator_token = &mut ctx.accounts.creator_token;
+    creator_token.open_limit_orders = creator_token.open_limit_orders.checked_sub(1).ok_or(error!(CustomError::MathOverflow))?;
+
+    emit!(LimitOrderFilled {
+        order: ctx.accounts.order.key(),
+        owner: ctx.accounts.owner.key(),
+        keeper: ctx.accounts.keeper.key(),
+        mint: ctx.accounts.mint.key(),
+        side,
+        amount,
+        lamports,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// Closing the order returns its escrowed lamports, keeper reward and rent to the owner
+pub fn cancel(ctx: Context<CancelLimitOrder>, _token_index: u16) -> Result<()> {
+    if ctx.accounts.order.side == LimitOrderSide::Sell {
+        let amount = ctx.accounts.order.amount;
+        let owner_ata = ctx.accounts.owner_ata.as_ref().ok_or(error!(CreatorTokenError::LimitOrderAccountsMissing))?;
+
+        let accounts = MintTo {
+            authority: ctx.accounts.mint_authority.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: owner_ata.to_account_info(),
+        };
+        let creator_token_key = ctx.accounts.creator_token.key();
+        let mint_authority_seeds: &[&[&[u8]]] = &[&[b"mint_authority", creator_token_key.as_ref(), &[ctx.accounts.creator_token.mint_authority_bump]]];
+        let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, mint_authority_seeds);
+        token_interface::mint_to(cpi_context, amount)?;
+
+        if ctx.accounts.creator_token.has_holder_hook {
+            let hook = HolderHookAccounts::new(
+                ctx.accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+                ctx.accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.holder_record.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.holder_stats.as_ref().map(|account| account.to_account_info()),
+                ctx.bumps.hook_authority,
+            )?;
+            ctx.accounts.creator_token.holder_count = record_holder_mint(
+                &hook,
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.mint.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                amount,
+            )?;
+        }
+
+        let creator_token = &mut ctx.accounts.creator_token;
+        creator_token.sell_order_escrow = creator_token.sell_order_escrow.checked_sub(amount).ok_or(error!(CustomError::MathOverflow))?;
+    }
+
+    let creator_token = &mut ctx.accounts.creator_token;
+    creator_token.open_limit_orders = creator_token.open_limit_orders.checked_sub(1).ok_or(error!(CustomError::MathOverflow))?;
+    Ok(())
+}
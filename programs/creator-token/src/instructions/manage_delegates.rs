@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::{ALL_PERMISSIONS, MAX_DELEGATES}, error::IdentityError, Delegate, Identity};
+
+// Only the creator manages delegates, delegates can't add or revoke each other
+#[derive(Accounts)]
+pub struct ManageDelegates<'info> {
+    pub creator : Signer<'info>,
+
+    #[account(mut, seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Account<'info, Identity>,
+}
+
+// Adds a delegate, or replaces the permissions of an existing one
+pub fn add(ctx: Context<ManageDelegates>, delegate: Pubkey, permissions: u8) -> Result<()> {
+    require!(permissions != 0 && permissions & !ALL_PERMISSIONS == 0, IdentityError::InvalidPermissions);
+
+    let delegates = &mut ctx.accounts.identity_proof.delegates;
+    match delegates.iter_mut().find(|existing| existing.key == delegate) {
+        Some(existing) => existing.permissions = permissions,
+        None => {
+            require!(delegates.len() < MAX_DELEGATES as usize, IdentityError::TooManyDelegates);
+            delegates.push(Delegate { key: delegate, permissions });
+        }
+    }
+
+    Ok(())
+}
+
+pub fn revoke(ctx: Context<ManageDelegates>, delegate: Pubkey) -> Result<()> {
+    let delegates = &mut ctx.accounts.identity_proof.delegates;
+    let position = delegates
+        .iter()
+        .position(|existing| existing.key == delegate)
+        .ok_or(error!(IdentityError::DelegateNotFound))?;
+    delegates.remove(position);
+
+    Ok(())
+}
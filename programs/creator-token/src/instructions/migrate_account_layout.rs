@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::CustomError, helpers::grow_account, CreatorToken, Identity};
+
+// Accounts created before the fields appended to `Identity` and `CreatorToken` existed are too
+// short to load. These grow them to the current layout, anyone can crank them. Before the
+// upgrade an identity could only own the single creator token at index 0.
+#[derive(Accounts)]
+pub struct MigrateIdentity<'info> {
+    #[account(mut)]
+    pub payer : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    /// CHECK: Old layout, deserialized in the handler once grown
+    #[account(mut, owner = crate::ID, seeds=[b"identity", creator.key().as_ref()], bump)]
+    pub identity_proof : UncheckedAccount<'info>,
+
+    /// CHECK: Only checked for existence, the identity's pre-upgrade creator token
+    #[account(seeds=[b"creator_token", identity_proof.key().as_ref()], bump)]
+    pub creator_token : UncheckedAccount<'info>,
+
+    pub system_program : Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCreatorToken<'info> {
+    #[account(mut)]
+    pub payer : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    /// CHECK: Only used for seeds, it may still have the old layout itself
+    #[account(seeds=[b"identity", creator.key().as_ref()], bump)]
+    pub identity_proof : UncheckedAccount<'info>,
+
+    /// CHECK: Old layout, deserialized in the handler once grown
+    #[account(mut, owner = crate::ID, seeds=[b"creator_token", identity_proof.key().as_ref()], bump)]
+    pub creator_token : UncheckedAccount<'info>,
+
+    /// CHECK: Only its owner is read, the token program the mint was created with
+    #[account(seeds=[b"owner", identity_proof.key().as_ref()], bump)]
+    pub mint : UncheckedAccount<'info>,
+
+    pub system_program : Program<'info, System>,
+}
+
+pub fn identity(ctx: Context<MigrateIdentity>) -> Result<()> {
+    let identity_info = ctx.accounts.identity_proof.to_account_info();
+    let grown = grow_account(
+        ctx.accounts.payer.to_account_info(),
+        identity_info.clone(),
+        ctx.accounts.system_program.to_account_info(),
+        8 + Identity::INIT_SPACE,
+    )?;
+    require!(grown, CustomError::AccountAlreadyMigrated);
+
+    // the zeroed bytes read as an active identity without delegates
+    let mut identity = Identity::try_deserialize(&mut &identity_info.try_borrow_data()?[..])?;
+    let has_token = u16::from(*ctx.accounts.creator_token.owner == crate::ID);
+    identity.token_count = has_token;
+    identity.active_tokens = has_token;
+    identity.try_serialize(&mut &mut identity_info.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
+
+pub fn creator_token(ctx: Context<MigrateCreatorToken>) -> Result<()> {
+    let grown = grow_creator_token(
+        ctx.accounts.payer.to_account_info(),
+        ctx.accounts.creator_token.to_account_info(),
+        &ctx.accounts.mint,
+        ctx.accounts.system_program.to_account_info(),
+    )?;
+    require!(grown, CustomError::AccountAlreadyMigrated);
+
+    Ok(())
+}
+
+// Grows a pre-upgrade creator token to the current layout, returns false if it already has it.
+// Also used by migrate_mint_authority, which runs on pre-upgrade creator tokens too
+pub fn grow_creator_token<'info>(
+    payer: AccountInfo<'info>,
+    creator_token_info: AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<bool> {
+    let grown = grow_account(payer, creator_token_info.clone(), system_program, 8 + CreatorToken::INIT_SPACE)?;
+    if !grown {
+        return Ok(false);
+    }
+
+    // the zeroed bytes read as index 0 with every later feature off
+    let mut creator_token = CreatorToken::try_deserialize(&mut &creator_token_info.try_borrow_data()?[..])?;
+    creator_token.token_program = *mint.owner;
+    creator_token.try_serialize(&mut &mut creator_token_info.try_borrow_mut_data()?[..])?;
+
+    Ok(true)
+}
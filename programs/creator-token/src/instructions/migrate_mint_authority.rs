@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self,
+    spl_pod::optional_keys::OptionalNonZeroPubkey,
+    spl_token_2022::{
+        extension::{mint_close_authority::MintCloseAuthority, BaseStateWithExtensions, StateWithExtensions},
+        instruction::AuthorityType,
+        state::Mint as MintState,
+    },
+    spl_token_metadata_interface::state::TokenMetadata,
+    token_metadata_update_authority, Mint, SetAuthority, TokenInterface, TokenMetadataUpdateAuthority,
+};
+
+use crate::{helpers::token_index_seed, instructions::migrate_account_layout::grow_creator_token, CreatorToken};
+
+// Moves a mint created under the old global `[b"mint_authority"]` PDA over to
+// its own `[b"mint_authority", creator_token]` PDA. Anyone can crank it, it only
+// ever hands the authorities to a PDA of this program. Creator tokens that still
+// have the pre-upgrade layout are grown to the current one first.
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct MigrateMintAuthority<'info> {
+    // pays the rent of a creator token that has to grow
+    #[account(mut)]
+    pub payer : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    /// CHECK: Only used for seeds, it may still have the old layout itself
+    #[account(seeds=[b"identity", creator.key().as_ref()], bump)]
+    pub identity_proof : UncheckedAccount<'info>,
+
+    /// CHECK: Old or current layout, deserialized in the handler once grown
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
+        bump
+    )]
+    pub creator_token : UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        mint::authority = legacy_mint_authority,
+        mint::token_program = token_program,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds=[b"mint_authority"], bump)]
+    pub legacy_mint_authority: SystemAccount<'info>,
+
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    pub token_program : Interface<'info, TokenInterface>,
+    pub system_program : Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateMintAuthority>, _token_index: u16) -> Result<()> {
+    let creator_token_info = ctx.accounts.creator_token.to_account_info();
+    grow_creator_token(
+        ctx.accounts.payer.to_account_info(),
+        creator_token_info.clone(),
+        &ctx.accounts.mint.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+    )?;
+    let mut creator_token = CreatorToken::try_deserialize(&mut &creator_token_info.try_borrow_data()?[..])?;
+
+    let legacy_seeds: &[&[&[u8]]] = &[&[b"mint_authority", &[ctx.bumps.legacy_mint_authority]]];
+    let new_authority = ctx.accounts.mint_authority.key();
+
+    // close authority and metadata only exist on mints created with those extensions
+    let (has_close_authority, has_metadata) = {
+        let mint_info = ctx.accounts.mint.to_account_info();
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<MintState>::unpack(&mint_data)?;
+        (
+            mint_state.get_extension::<MintCloseAuthority>().is_ok(),
+            mint_state.get_variable_len_extension::<TokenMetadata>().is_ok(),
+        )
+    };
+
+    let mut authority_types = vec![AuthorityType::MintTokens, AuthorityType::FreezeAccount];
+    if has_close_authority {
+        authority_types.push(AuthorityType::CloseMint);
+    }
+    for authority_type in authority_types {
+        let accounts = SetAuthority {
+            current_authority: ctx.accounts.legacy_mint_authority.to_account_info(),
+            account_or_mint: ctx.accounts.mint.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, legacy_seeds);
+        token_interface::set_authority(cpi_context, authority_type, Some(new_authority))?;
+    }
+
+    if has_metadata {
+        let accounts = TokenMetadataUpdateAuthority {
+            program_id: ctx.accounts.token_program.to_account_info(),
+            metadata: ctx.accounts.mint.to_account_info(),
+            current_authority: ctx.accounts.legacy_mint_authority.to_account_info(),
+            new_authority: ctx.accounts.mint_authority.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, legacy_seeds);
+        token_metadata_update_authority(cpi_context, OptionalNonZeroPubkey(new_authority))?;
+    }
+
+    creator_token.mint_authority_bump = ctx.bumps.mint_authority;
+    creator_token.try_serialize(&mut &mut creator_token_info.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
@@ -1,13 +1,57 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod initialize;
 pub mod creator_identity;
 pub mod create_creator_token;
 pub mod buy_token;
+pub mod buy_many;
+pub mod gift_token;
+pub mod limit_order;
+pub mod dca_plan;
 pub mod token_price;
 pub mod sell_token;
+pub mod swap_creator_tokens;
+pub mod close_creator_token;
+pub mod close_creator_identity;
+pub mod update_creator_identity;
+pub mod moderate_identity;
+pub mod manage_delegates;
+pub mod pause_buys;
+pub mod update_token_metadata;
+pub mod migrate_mint_authority;
+pub mod migrate_account_layout;
+pub mod freeze_holder;
+pub mod sync_holder_count;
+pub mod update_config;
+pub mod withdraw_royalties;
+pub mod claim_vested;
+pub mod referrer;
+pub mod withdraw_platform_fees;
 
 pub use initialize::*;
 pub use creator_identity::*;
 pub use create_creator_token::*;
 pub use buy_token::*;
+pub use buy_many::*;
+pub use gift_token::*;
+pub use limit_order::*;
+pub use dca_plan::*;
 pub use token_price::*;
-pub use sell_token::*;
\ No newline at end of file
+pub use sell_token::*;
+pub use swap_creator_tokens::*;
+pub use close_creator_token::*;
+pub use close_creator_identity::*;
+pub use update_creator_identity::*;
+pub use moderate_identity::*;
+pub use manage_delegates::*;
+pub use pause_buys::*;
+pub use update_token_metadata::*;
+pub use migrate_mint_authority::*;
+pub use migrate_account_layout::*;
+pub use freeze_holder::*;
+pub use sync_holder_count::*;
+pub use update_config::*;
+pub use withdraw_royalties::*;
+pub use claim_vested::*;
+pub use referrer::*;
+pub use withdraw_platform_fees::*;
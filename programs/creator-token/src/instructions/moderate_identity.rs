@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::{AdminError, IdentityError}, Config, Identity, IdentityStatus};
+
+#[derive(Accounts)]
+pub struct ModerateIdentity<'info> {
+    pub admin : Signer<'info>,
+
+    #[account(seeds=[b"config"], bump=config.bump, has_one = admin @ AdminError::Unauthorized)]
+    pub config : Account<'info, Config>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Account<'info, Identity>,
+}
+
+pub fn suspend(ctx: Context<ModerateIdentity>, reason: u16) -> Result<()> {
+    let identity_proof = &mut ctx.accounts.identity_proof;
+    require!(identity_proof.status == IdentityStatus::Active, IdentityError::IdentitySuspended);
+
+    identity_proof.status = IdentityStatus::Suspended;
+    identity_proof.suspension_reason = reason;
+
+    Ok(())
+}
+
+pub fn reinstate(ctx: Context<ModerateIdentity>) -> Result<()> {
+    let identity_proof = &mut ctx.accounts.identity_proof;
+    require!(identity_proof.status == IdentityStatus::Suspended, IdentityError::IdentityNotSuspended);
+
+    identity_proof.status = IdentityStatus::Active;
+    identity_proof.suspension_reason = 0;
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::PERMISSION_PAUSE_BUYS, error::IdentityError, helpers::token_index_seed, CreatorToken, Identity};
+
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct PauseBuys<'info> {
+    // creator or a delegate with the pause permission
+    pub authority : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(
+        seeds=[b"identity", creator.key().as_ref()], 
+        bump=identity_proof.bump,
+        constraint = identity_proof.is_authorized(&authority.key(), PERMISSION_PAUSE_BUYS) @ IdentityError::NotAuthorized
+    )]
+    pub identity_proof : Account<'info, Identity>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
+        bump=creator_token.bump
+    )]
+    pub creator_token : Account<'info, CreatorToken>,
+}
+
+// Sells stay open while buys are paused
+pub fn handler(ctx: Context<PauseBuys>, _token_index: u16, paused: bool) -> Result<()> {
+    ctx.accounts.creator_token.buys_paused = paused;
+    Ok(())
+}
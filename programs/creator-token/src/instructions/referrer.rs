@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::{CreatorTokenError, CustomError}, events::ReferralFeesClaimed, Referrer};
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(mut)]
+    pub authority : Signer<'info>,
+
+    #[account(init, payer=authority, seeds=[b"referrer", authority.key().as_ref()], space=8+Referrer::INIT_SPACE, bump)]
+    pub referrer : Account<'info, Referrer>,
+
+    pub system_program : Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralFees<'info> {
+    #[account(mut)]
+    pub authority : Signer<'info>,
+
+    #[account(mut, seeds=[b"referrer", authority.key().as_ref()], bump=referrer.bump, has_one=authority)]
+    pub referrer : Account<'info, Referrer>,
+}
+
+pub fn register(ctx: Context<RegisterReferrer>) -> Result<()> {
+    ctx.accounts.referrer.authority = ctx.accounts.authority.key();
+    ctx.accounts.referrer.total_earned = 0;
+    ctx.accounts.referrer.total_claimed = 0;
+    ctx.accounts.referrer.bump = ctx.bumps.referrer;
+    Ok(())
+}
+
+pub fn claim(ctx: Context<ClaimReferralFees>) -> Result<()> {
+    let referrer = &mut ctx.accounts.referrer;
+    let amount = referrer.total_earned.checked_sub(referrer.total_claimed).ok_or(error!(CustomError::MathOverflow))?;
+    require!(amount > 0, CreatorTokenError::NothingToClaim);
+
+    // the account is owned by this program, its rent exemption stays untouched
+    referrer.sub_lamports(amount)?;
+    ctx.accounts.authority.add_lamports(amount)?;
+    referrer.total_claimed = referrer.total_earned;
+
+    emit!(ReferralFeesClaimed {
+        referrer: ctx.accounts.authority.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
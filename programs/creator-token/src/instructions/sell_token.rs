@@ -1,18 +1,27 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{token_2022::{burn, Burn}, token_interface::{Mint, TokenAccount, TokenInterface}};
+use anchor_spl::token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface};
 use anchor_lang::{
     system_program::{transfer, Transfer},
 };
 
-use crate::{helpers::{get_selling_return}, CreatorToken, Identity};
+use holder_hook::program::HolderHook;
+
+use crate::{
+    error::CreatorTokenError,
+    events::{ReferralFeePaid, SellTaxPaid},
+    helpers::{check_deadline, get_sell_tax, get_selling_return, get_trade_fee, pay_trade_fee, record_holder_burn, token_index_seed, HolderHookAccounts},
+    Config, CreatorToken, HolderPosition, Identity, Referrer,
+};
 
 #[derive(Accounts)]
+#[instruction(token_index: u16)]
 pub struct SellToken<'info> {
     // seller
     #[account(mut)]
     pub seller : Signer<'info>,
 
-    /// CHECK: Needed to derive proper accounts for validation
+    /// CHECK: Needed to derive proper accounts for validation, receives the sell tax if the token says so
+    #[account(mut)]
     pub creator : AccountInfo<'info>,
 
     // seller ata 
@@ -20,7 +29,8 @@ pub struct SellToken<'info> {
         mut,
         associated_token::mint=mint,
         associated_token::authority=seller,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program,
+        constraint = !seller_ata.is_frozen() @ CreatorTokenError::TokenAccountFrozen
     )]
     pub seller_ata : InterfaceAccount<'info, TokenAccount>,
 
@@ -29,7 +39,8 @@ pub struct SellToken<'info> {
     pub identity_proof : Account<'info, Identity>,
 
     #[account(
-        seeds=[b"creator_token", identity_proof.key().as_ref()], 
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
         // has_one=mint,
         // has_one=vault,
         // has_one=creator,
@@ -38,7 +49,7 @@ pub struct SellToken<'info> {
     pub creator_token : Account<'info, CreatorToken>,
 
     // mint authority
-    #[account(seeds=[b"mint_authority"], bump=creator_token.mint_authority_bump)]
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
     pub mint_authority: SystemAccount<'info>,
 
     // mint
@@ -46,7 +57,7 @@ pub struct SellToken<'info> {
         mut,
         mint::authority=mint_authority,
         mint::freeze_authority=mint_authority,
-        seeds=[b"owner", identity_proof.key().as_ref()],
+        seeds=[b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
         bump=creator_token.mint_bump
     )]
     pub mint: InterfaceAccount<'info, Mint>,
@@ -54,18 +65,53 @@ pub struct SellToken<'info> {
     // token vault
     #[account(
         mut,
-        seeds=[b"vault", identity_proof.key().as_ref()],
+        seeds=[b"vault", identity_proof.key().as_ref(), &token_index_seed(token_index)],
         bump=creator_token.vault_bump
     )]
     pub vault : SystemAccount<'info>,
 
+    #[account(address = creator_token.token_program)]
     pub token_program : Interface<'info, TokenInterface>,
-    pub system_program : Program<'info, System>
-    
+    pub system_program : Program<'info, System>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the seller, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+
+    #[account(seeds=[b"config"], bump=config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds=[b"fee_vault"], bump=config.fee_vault_bump)]
+    pub fee_vault: SystemAccount<'info>,
+
+    // affiliate that brought the seller in, takes a share of the trading fee
+    #[account(
+        mut,
+        seeds=[b"referrer", referrer.authority.as_ref()],
+        bump=referrer.bump,
+        constraint = referrer.authority != seller.key() @ CreatorTokenError::SelfReferral
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+
+    /// CHECK: Seller's HolderPosition, required for tokens with a sell tax. It doesn't exist
+    /// when the seller never bought with buy_creator_token, those tokens pay the full tax
+    #[account(mut, seeds=[b"position", creator_token.key().as_ref(), seller.key().as_ref()], bump)]
+    pub position: Option<UncheckedAccount<'info>>,
 }
 
-pub fn handler(ctx: Context<SellToken>, tokens_to_sell: u64) -> Result<()> {
-    
+pub fn handler(ctx: Context<SellToken>, token_index: u16, tokens_to_sell: u64, expires_at: Option<i64>) -> Result<()> {
+    check_deadline(expires_at)?;
+
     // burn tokens from seller_ata
     let required_accounts = Burn {
         authority: ctx.accounts.seller.to_account_info(),
@@ -78,8 +124,26 @@ pub fn handler(ctx: Context<SellToken>, tokens_to_sell: u64) -> Result<()> {
     );
     burn(cpi_context_burn, tokens_to_sell)?;
 
+    // the transfer hook doesn't run on burns, report the new balance
+    if ctx.accounts.creator_token.has_holder_hook {
+        let hook = HolderHookAccounts::new(
+            ctx.accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+            ctx.accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.holder_record.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.holder_stats.as_ref().map(|account| account.to_account_info()),
+            ctx.bumps.hook_authority,
+        )?;
+        ctx.accounts.creator_token.holder_count = record_holder_burn(
+            &hook,
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.seller.to_account_info(),
+            tokens_to_sell,
+        )?;
+    }
+
     // calculate cost for the tokens burned
-    let current_supply: u64 = ctx.accounts.mint.supply;
+    let current_supply: u64 = ctx.accounts.creator_token.curve_supply(ctx.accounts.mint.supply)?;
+    require!(tokens_to_sell <= current_supply, CreatorTokenError::InsufficientCurveSupply);
     let base_price: u64 = ctx.accounts.creator_token.base_price;
     let slope: u64 = ctx.accounts.creator_token.slope;
     let decimals: u8 = ctx.accounts.mint.decimals;  
@@ -92,16 +156,88 @@ pub fn handler(ctx: Context<SellToken>, tokens_to_sell: u64) -> Result<()> {
     )?;
 
     // msg!("CHECKING TOKEN COST : {}", token_cost);
-    
+
+    // trading fee comes out of the return, paid straight from the vault
+    let fee = get_trade_fee(&ctx.accounts.config, token_cost, ctx.accounts.referrer.is_some())?;
+    let identity_proof_key = ctx.accounts.identity_proof.key();
+    let index_seed = token_index_seed(token_index);
+    let transfer_signing_seeds: &[&[&[u8]]] = &[&[b"vault", identity_proof_key.as_ref(), &index_seed, &[ctx.accounts.creator_token.vault_bump]]];
+    pay_trade_fee(
+        &fee,
+        ctx.accounts.vault.to_account_info(),
+        ctx.accounts.fee_vault.to_account_info(),
+        ctx.accounts.referrer.as_mut(),
+        ctx.accounts.system_program.to_account_info(),
+        transfer_signing_seeds,
+    )?;
+    if let Some(referrer) = &ctx.accounts.referrer {
+        emit!(ReferralFeePaid {
+            referrer: referrer.authority,
+            trader: ctx.accounts.seller.key(),
+            mint: ctx.accounts.mint.key(),
+            trade_lamports: token_cost,
+            fee: fee.total(),
+            referral_amount: fee.referral,
+            is_buy: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+
+    // holding-period tax on the tokens the seller bought, the seller's oldest tokens aren't told
+    // apart so the position's weighted age applies to all of them. Tokens beyond the position
+    // were transferred in or gifted and pay the full rate, otherwise a transfer would reset the tax to 0
+    let tax = if ctx.accounts.creator_token.has_sell_tax() {
+        let position_info = ctx.accounts.position.as_ref().ok_or(error!(CreatorTokenError::HolderPositionMissing))?;
+        let (tracked, acquired_at) = if position_info.data_is_empty() {
+            (0, 0)
+        } else {
+            require_keys_eq!(*position_info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+            let mut data = position_info.try_borrow_mut_data()?;
+            let mut position = HolderPosition::try_deserialize(&mut &data[..])?;
+            // tokens sent away leave the position with them, or tokens transferred in later would
+            // take their place and sell at the old age
+            let covered = position.amount.min(ctx.accounts.seller_ata.amount);
+            let tracked = covered.min(tokens_to_sell);
+            position.amount = covered - tracked;
+            position.try_serialize(&mut &mut data[..])?;
+            (tracked, position.acquired_at)
+        };
+
+        let creator_token = &ctx.accounts.creator_token;
+        let now = Clock::get()?.unix_timestamp;
+        let held_seconds = now.saturating_sub(acquired_at);
+        let tax = get_sell_tax(token_cost, tokens_to_sell, tracked, creator_token.sell_tax_bps, creator_token.sell_tax_decay_seconds, held_seconds)?
+            .min(token_cost - fee.total());
+
+        // left in the vault the tax backs the remaining supply
+        if tax > 0 && creator_token.sell_tax_to_creator {
+            let accounts = Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.creator.to_account_info(),
+            };
+            transfer(CpiContext::new_with_signer(ctx.accounts.system_program.to_account_info(), accounts, transfer_signing_seeds), tax)?;
+        }
+        if tax > 0 {
+            emit!(SellTaxPaid {
+                mint: ctx.accounts.mint.key(),
+                seller: ctx.accounts.seller.key(),
+                tax,
+                to_creator: creator_token.sell_tax_to_creator,
+                timestamp: now,
+            });
+        }
+        tax
+    } else {
+        0
+    };
+
     // transfer equivalent lamports from vault to signer
     let transfer_accounts = Transfer {
         from: ctx.accounts.vault.to_account_info(),
         to: ctx.accounts.seller.to_account_info()
     };
-    let identity_proof_key = ctx.accounts.identity_proof.key();
-    let transfer_signing_seeds: &[&[&[u8]]] = &[&[b"vault", identity_proof_key.as_ref(), &[ctx.accounts.creator_token.vault_bump]]];
     let cpi_context_transfer = CpiContext::new_with_signer(ctx.accounts.system_program.to_account_info(), transfer_accounts,transfer_signing_seeds);
-    transfer( cpi_context_transfer , token_cost)
+    transfer( cpi_context_transfer , token_cost - fee.total() - tax)
 
     // Ok(())
 }
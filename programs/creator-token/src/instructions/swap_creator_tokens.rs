@@ -0,0 +1,336 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface},
+};
+
+use holder_hook::program::HolderHook;
+
+use crate::{
+    constants::MAX_BPS,
+    error::{CreatorTokenError, CustomError, IdentityError},
+    events::CreatorTokensSwapped,
+    helpers::{
+        check_deadline, execute_curve_buy, get_buying_cost, get_selling_return, get_tokens_for_lamports, get_trade_fee,
+        record_holder_burn, token_index_seed, BuyFunding, CurveBuyAccounts, HolderHookAccounts, LaunchAccess,
+    },
+    Config, CreatorToken, Identity, IdentityStatus,
+};
+
+// `_in` is the creator token sold back to its curve, `_out` the one bought with the proceeds
+#[derive(Accounts)]
+#[instruction(token_index_in: u16, token_index_out: u16)]
+pub struct SwapCreatorTokens<'info> {
+    #[account(mut)]
+    pub user : Signer<'info>,
+
+    #[account(seeds=[b"config"], bump=config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut, seeds=[b"fee_vault"], bump=config.fee_vault_bump)]
+    pub fee_vault: SystemAccount<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator_in : AccountInfo<'info>,
+
+    // sells are allowed while an identity is suspended
+    #[account(seeds=[b"identity", creator_in.key().as_ref()], bump=identity_proof_in.bump)]
+    pub identity_proof_in : Box<Account<'info, Identity>>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof_in.key().as_ref(), &token_index_seed(token_index_in)],
+        bump=creator_token_in.bump
+    )]
+    pub creator_token_in : Box<Account<'info, CreatorToken>>,
+
+    #[account(
+        mut,
+        seeds=[b"owner", identity_proof_in.key().as_ref(), &token_index_seed(token_index_in)],
+        bump=creator_token_in.mint_bump,
+        mint::token_program = token_program_in
+    )]
+    pub mint_in : Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds=[b"vault", identity_proof_in.key().as_ref(), &token_index_seed(token_index_in)],
+        bump=creator_token_in.vault_bump
+    )]
+    pub vault_in : SystemAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_in,
+        constraint = !user_ata_in.is_frozen() @ CreatorTokenError::TokenAccountFrozen
+    )]
+    pub user_ata_in : Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator_out : AccountInfo<'info>,
+
+    #[account(
+        seeds=[b"identity", creator_out.key().as_ref()],
+        bump=identity_proof_out.bump,
+        constraint = identity_proof_out.status == IdentityStatus::Active @ IdentityError::IdentitySuspended
+    )]
+    pub identity_proof_out : Box<Account<'info, Identity>>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof_out.key().as_ref(), &token_index_seed(token_index_out)],
+        bump=creator_token_out.bump,
+        constraint = !creator_token_out.buys_paused @ CreatorTokenError::BuysPaused,
+        constraint = creator_token_out.key() != creator_token_in.key() @ CreatorTokenError::SwapSameToken
+    )]
+    pub creator_token_out : Box<Account<'info, CreatorToken>>,
+
+    #[account(
+        mut,
+        seeds=[b"owner", identity_proof_out.key().as_ref(), &token_index_seed(token_index_out)],
+        bump=creator_token_out.mint_bump,
+        mint::token_program = token_program_out
+    )]
+    pub mint_out : Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(seeds=[b"mint_authority", creator_token_out.key().as_ref()], bump=creator_token_out.mint_authority_bump)]
+    pub mint_authority_out : SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"vault", identity_proof_out.key().as_ref(), &token_index_seed(token_index_out)],
+        bump=creator_token_out.vault_bump
+    )]
+    pub vault_out : SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_out,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_out,
+        constraint = !user_ata_out.is_frozen() @ CreatorTokenError::TokenAccountFrozen
+    )]
+    pub user_ata_out : Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = creator_token_in.token_program)]
+    pub token_program_in : Interface<'info, TokenInterface>,
+    #[account(address = creator_token_out.token_program)]
+    pub token_program_out : Interface<'info, TokenInterface>,
+    pub associated_token_program : Program<'info, AssociatedToken>,
+    pub system_program : Program<'info, System>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the user for `mint_in`, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record_in: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of `mint_in`, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats_in: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the user for `mint_out`, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record_out: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of `mint_out`, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats_out: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_index_in: u16, token_index_out: u16)]
+pub struct SwapQuote<'info> {
+    #[account(seeds=[b"config"], bump=config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator_in : AccountInfo<'info>,
+
+    #[account(seeds=[b"identity", creator_in.key().as_ref()], bump=identity_proof_in.bump)]
+    pub identity_proof_in : Box<Account<'info, Identity>>,
+
+    #[account(
+        seeds=[b"creator_token", identity_proof_in.key().as_ref(), &token_index_seed(token_index_in)],
+        bump=creator_token_in.bump
+    )]
+    pub creator_token_in : Box<Account<'info, CreatorToken>>,
+
+    #[account(seeds=[b"owner", identity_proof_in.key().as_ref(), &token_index_seed(token_index_in)], bump=creator_token_in.mint_bump)]
+    pub mint_in : Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator_out : AccountInfo<'info>,
+
+    #[account(seeds=[b"identity", creator_out.key().as_ref()], bump=identity_proof_out.bump)]
+    pub identity_proof_out : Box<Account<'info, Identity>>,
+
+    #[account(
+        seeds=[b"creator_token", identity_proof_out.key().as_ref(), &token_index_seed(token_index_out)],
+        bump=creator_token_out.bump
+    )]
+    pub creator_token_out : Box<Account<'info, CreatorToken>>,
+
+    #[account(seeds=[b"owner", identity_proof_out.key().as_ref(), &token_index_seed(token_index_out)], bump=creator_token_out.mint_bump)]
+    pub mint_out : Box<InterfaceAccount<'info, Mint>>,
+}
+
+struct SwapAmounts {
+    // lamports the sold tokens return from the `_in` curve
+    sell_return: u64,
+    amount_out: u64,
+    // what's left of the proceeds after the buy and its fee, paid out to the user
+    refund: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SwapQuoteAmounts {
+    pub amount_out: u64,
+    // lamports of the proceeds `amount_out` doesn't use, refunded to the user
+    pub refund: u64,
+}
+
+// The trading fee is charged once, by the buy leg, so the whole sell return goes into the buy
+fn get_swap_amounts(
+    config: &Config,
+    creator_token_in: &CreatorToken,
+    mint_in: &Mint,
+    creator_token_out: &CreatorToken,
+    mint_out: &Mint,
+    amount_in: u64,
+) -> Result<SwapAmounts> {
+    let supply_in = creator_token_in.curve_supply(mint_in.supply)?;
+    require!(amount_in <= supply_in, CreatorTokenError::InsufficientCurveSupply);
+    let sell_return = get_selling_return(amount_in, supply_in, creator_token_in.base_price, creator_token_in.slope, mint_in.decimals)?;
+
+    // leave room for the trading fee on top of the curve cost
+    let fee_bps = config.trade_fee_bps as u128;
+    let curve_budget = u64::try_from(sell_return as u128 * MAX_BPS as u128 / (MAX_BPS as u128 + fee_bps))
+        .map_err(|_| error!(CustomError::MathOverflow))?;
+    let supply_out = creator_token_out.curve_supply(mint_out.supply)?;
+    let amount_out = get_tokens_for_lamports(curve_budget, supply_out, creator_token_out.base_price, creator_token_out.slope, mint_out.decimals)?;
+    let buy_cost = get_buying_cost(amount_out, supply_out, creator_token_out.base_price, creator_token_out.slope, mint_out.decimals)?;
+    let fee = get_trade_fee(config, buy_cost, false)?;
+    let refund = sell_return
+        .checked_sub(buy_cost + fee.total())
+        .ok_or(error!(CustomError::MathOverflow))?;
+
+    Ok(SwapAmounts { sell_return, amount_out, refund })
+}
+
+pub fn quote(ctx: Context<SwapQuote>, _token_index_in: u16, _token_index_out: u16, amount_in: u64) -> Result<SwapQuoteAmounts> {
+    let amounts = get_swap_amounts(
+        &ctx.accounts.config,
+        &ctx.accounts.creator_token_in,
+        &ctx.accounts.mint_in,
+        &ctx.accounts.creator_token_out,
+        &ctx.accounts.mint_out,
+        amount_in,
+    )?;
+    Ok(SwapQuoteAmounts { amount_out: amounts.amount_out, refund: amounts.refund })
+}
+
+pub fn handler(
+    ctx: Context<SwapCreatorTokens>,
+    token_index_in: u16,
+    _token_index_out: u16,
+    amount_in: u64,
+    min_amount_out: u64,
+    expires_at: Option<i64>,
+) -> Result<()> {
+    check_deadline(expires_at)?;
+    ctx.accounts.creator_token_in.check_untaxed()?;
+
+    let amounts = get_swap_amounts(
+        &ctx.accounts.config,
+        &ctx.accounts.creator_token_in,
+        &ctx.accounts.mint_in,
+        &ctx.accounts.creator_token_out,
+        &ctx.accounts.mint_out,
+        amount_in,
+    )?;
+    require!(amounts.amount_out > 0 && amounts.amount_out >= min_amount_out, CreatorTokenError::SlippageExceeded);
+
+    let accounts = Burn {
+        authority: ctx.accounts.user.to_account_info(),
+        from: ctx.accounts.user_ata_in.to_account_info(),
+        mint: ctx.accounts.mint_in.to_account_info(),
+    };
+    burn(CpiContext::new(ctx.accounts.token_program_in.to_account_info(), accounts), amount_in)?;
+
+    // the transfer hook doesn't see the burn
+    if ctx.accounts.creator_token_in.has_holder_hook {
+        let hook = HolderHookAccounts::new(
+            ctx.accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+            ctx.accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.holder_record_in.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.holder_stats_in.as_ref().map(|account| account.to_account_info()),
+            ctx.bumps.hook_authority,
+        )?;
+        ctx.accounts.creator_token_in.holder_count =
+            record_holder_burn(&hook, ctx.accounts.mint_in.to_account_info(), ctx.accounts.user.to_account_info(), amount_in)?;
+    }
+
+    // the proceeds go from vault to vault, the user's wallet only gets what the buy doesn't use
+    let identity_proof_in_key = ctx.accounts.identity_proof_in.key();
+    let index_seed_in = token_index_seed(token_index_in);
+    let vault_in_seeds: &[&[&[u8]]] = &[&[b"vault", identity_proof_in_key.as_ref(), &index_seed_in, &[ctx.accounts.creator_token_in.vault_bump]]];
+    let accounts = &mut *ctx.accounts;
+    execute_curve_buy(
+        CurveBuyAccounts {
+            payer: accounts.user.to_account_info(),
+            funding: BuyFunding::Vault(accounts.vault_in.to_account_info(), vault_in_seeds),
+            recipient: accounts.user.to_account_info(),
+            recipient_ata: accounts.user_ata_out.to_account_info(),
+            creator_token: &mut accounts.creator_token_out,
+            mint: &accounts.mint_out,
+            mint_authority: accounts.mint_authority_out.to_account_info(),
+            vault: accounts.vault_out.to_account_info(),
+            config: &accounts.config,
+            fee_vault: accounts.fee_vault.to_account_info(),
+            referrer: None,
+            token_program: accounts.token_program_out.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            holder_hook_program: accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+            hook_authority: accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+            holder_record: accounts.holder_record_out.as_ref().map(|account| account.to_account_info()),
+            holder_stats: accounts.holder_stats_out.as_ref().map(|account| account.to_account_info()),
+            hook_authority_bump: ctx.bumps.hook_authority,
+            launch_buys: None,
+            launch_buys_bump: None,
+            position: None,
+            position_bump: None,
+            badge: None,
+        },
+        amounts.amount_out,
+        Some(amounts.sell_return),
+        LaunchAccess::OpenOnly,
+    )?;
+    if amounts.refund > 0 {
+        let transfer_accounts = Transfer {
+            from: accounts.vault_in.to_account_info(),
+            to: accounts.user.to_account_info(),
+        };
+        transfer(CpiContext::new_with_signer(accounts.system_program.to_account_info(), transfer_accounts, vault_in_seeds), amounts.refund)?;
+    }
+
+    emit!(CreatorTokensSwapped {
+        user: accounts.user.key(),
+        mint_in: accounts.mint_in.key(),
+        mint_out: accounts.mint_out.key(),
+        amount_in,
+        amount_out: amounts.amount_out,
+        lamports: amounts.sell_return,
+        refund: amounts.refund,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
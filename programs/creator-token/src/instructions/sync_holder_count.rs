@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use holder_hook::HolderStats;
+
+use crate::{helpers::token_index_seed, CreatorToken, Identity};
+
+// Wallet-to-wallet transfers only reach the holder hook, anyone can copy its count over
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct SyncHolderCount<'info> {
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(seeds=[b"identity", creator.key().as_ref()], bump=identity_proof.bump)]
+    pub identity_proof : Account<'info, Identity>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.bump
+    )]
+    pub creator_token : Account<'info, CreatorToken>,
+
+    #[account(
+        seeds=[b"holder_stats", creator_token.mint.as_ref()],
+        bump=holder_stats.bump,
+        seeds::program = holder_hook::ID
+    )]
+    pub holder_stats : Account<'info, HolderStats>,
+}
+
+pub fn handler(ctx: Context<SyncHolderCount>, _token_index: u16) -> Result<()> {
+    ctx.accounts.creator_token.holder_count = ctx.accounts.holder_stats.holder_count;
+
+    Ok(())
+}
@@ -1,9 +1,10 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::Mint;
 
-use crate::{helpers::{get_buying_cost, get_selling_return}, CreatorToken, Identity};
+use crate::{error::CreatorTokenError, helpers::{get_buying_cost, get_selling_return, token_index_seed}, CreatorToken, Identity};
 
 #[derive(Accounts)]
+#[instruction(token_index: u16)]
 pub struct TokenPrice<'info> {
     /// CHECK: Needed to derive proper accounts for validation
     pub creator : AccountInfo<'info>,
@@ -12,25 +13,25 @@ pub struct TokenPrice<'info> {
     pub identity_proof : Account<'info, Identity>,
 
     #[account(
-        seeds=[b"creator_token", identity_proof.key().as_ref()], 
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
         bump=creator_token.bump
     )]
     pub creator_token : Account<'info, CreatorToken>,
 
-    #[account(seeds=[b"mint_authority"], bump=creator_token.mint_authority_bump)]
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
     pub mint_authority: SystemAccount<'info>,
 
     #[account(
         mint::authority = mint_authority,
         mint::freeze_authority = mint_authority,
-        seeds = [b"owner", identity_proof.key().as_ref()],
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
         bump=creator_token.mint_bump
     )]
     pub mint: InterfaceAccount<'info, Mint>,
 }
 
-pub fn buying_cost(ctx: Context<TokenPrice>, tokens_to_buy: u64) -> Result<u64> {
-    let current_supply: u64 = ctx.accounts.mint.supply;
+pub fn buying_cost(ctx: Context<TokenPrice>, _token_index: u16, tokens_to_buy: u64) -> Result<u64> {
+    let current_supply: u64 = ctx.accounts.creator_token.curve_supply(ctx.accounts.mint.supply)?;
     let base_price: u64 = ctx.accounts.creator_token.base_price;
     let slope: u64 = ctx.accounts.creator_token.slope;
     let decimals: u8 = ctx.accounts.mint.decimals;  
@@ -46,8 +47,9 @@ pub fn buying_cost(ctx: Context<TokenPrice>, tokens_to_buy: u64) -> Result<u64>
     Ok(total_price)
 }
 
-pub fn selling_return(ctx: Context<TokenPrice>, tokens_to_buy: u64) -> Result<u64> {
-    let current_supply: u64 = ctx.accounts.mint.supply;
+pub fn selling_return(ctx: Context<TokenPrice>, _token_index: u16, tokens_to_buy: u64) -> Result<u64> {
+    let current_supply: u64 = ctx.accounts.creator_token.curve_supply(ctx.accounts.mint.supply)?;
+    require!(tokens_to_buy <= current_supply, CreatorTokenError::InsufficientCurveSupply);
     let base_price: u64 = ctx.accounts.creator_token.base_price;
     let slope: u64 = ctx.accounts.creator_token.slope;
     let decimals: u8 = ctx.accounts.mint.decimals;  
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_BPS, error::AdminError, Config};
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin : Signer<'info>,
+
+    #[account(mut, seeds=[b"config"], bump=config.bump, has_one = admin @ AdminError::Unauthorized)]
+    pub config : Account<'info, Config>,
+}
+
+// Only applies to tokens created afterwards, existing transfer fees are left as they are
+pub fn max_royalty_bps(ctx: Context<UpdateConfig>, max_royalty_bps: u16) -> Result<()> {
+    require!(max_royalty_bps <= MAX_BPS, AdminError::InvalidBps);
+    ctx.accounts.config.max_royalty_bps = max_royalty_bps;
+    Ok(())
+}
+
+pub fn trade_fees(ctx: Context<UpdateConfig>, trade_fee_bps: u16, referral_share_bps: u16) -> Result<()> {
+    require!(trade_fee_bps <= MAX_BPS && referral_share_bps <= MAX_BPS, AdminError::InvalidBps);
+    ctx.accounts.config.trade_fee_bps = trade_fee_bps;
+    ctx.accounts.config.referral_share_bps = referral_share_bps;
+    Ok(())
+}
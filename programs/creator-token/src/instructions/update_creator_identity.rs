@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::PERMISSION_PROFILE, error::IdentityError, Identity, IdentityStatus};
+
+#[derive(Accounts)]
+pub struct UpdateCreatorIdentity<'info> {
+    // creator or a delegate with the profile permission
+    pub authority : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"identity", creator.key().as_ref()], 
+        bump=identity_proof.bump,
+        constraint = identity_proof.status == IdentityStatus::Active @ IdentityError::IdentitySuspended,
+        constraint = identity_proof.is_authorized(&authority.key(), PERMISSION_PROFILE) @ IdentityError::NotAuthorized
+    )]
+    pub identity_proof : Account<'info, Identity>,
+}
+
+pub fn handler(ctx: Context<UpdateCreatorIdentity>, user_name : String, proof_url : String) -> Result<()> {
+    require!(user_name.len() <= 50, IdentityError::NameTooLong);
+    require!(proof_url.len() <= 200, IdentityError::UrlTooLong);
+
+    ctx.accounts.identity_proof.creator_name = user_name;
+    ctx.accounts.identity_proof.proof_url = proof_url;
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{
+        spl_token_2022::{extension::{BaseStateWithExtensions, StateWithExtensions}, state::Mint as MintState},
+        spl_token_metadata_interface::state::{Field, TokenMetadata},
+        token_metadata_update_field, Mint, TokenInterface, TokenMetadataUpdateField,
+    },
+};
+
+use crate::{
+    constants::PERMISSION_PROFILE,
+    error::{CreatorTokenError, IdentityError},
+    helpers::{token_index_seed, top_up_rent, validate_token_name, validate_token_symbol, validate_token_uri},
+    CreatorToken, Identity, IdentityStatus,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TokenMetadataField {
+    Name,
+    Symbol,
+    Uri,
+}
+
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct UpdateTokenMetadata<'info> {
+    // creator or a delegate with the profile permission, pays for a bigger mint
+    #[account(mut)]
+    pub authority : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(
+        seeds=[b"identity", creator.key().as_ref()], 
+        bump=identity_proof.bump,
+        constraint = identity_proof.status == IdentityStatus::Active @ IdentityError::IdentitySuspended,
+        constraint = identity_proof.is_authorized(&authority.key(), PERMISSION_PROFILE) @ IdentityError::NotAuthorized
+    )]
+    pub identity_proof : Account<'info, Identity>,
+
+    #[account(
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
+        bump=creator_token.bump
+    )]
+    pub creator_token : Account<'info, CreatorToken>,
+
+    #[account(
+        mut,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    #[account(
+        constraint = token_program.key() == Token2022::id() @ CreatorTokenError::Token2022Required,
+        address = creator_token.token_program
+    )]
+    pub token_program : Interface<'info, TokenInterface>,
+    pub system_program : Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<UpdateTokenMetadata>, _token_index: u16, field: TokenMetadataField, value: String) -> Result<()> {
+    let field = match field {
+        TokenMetadataField::Name => {
+            validate_token_name(&value)?;
+            Field::Name
+        }
+        TokenMetadataField::Symbol => {
+            validate_token_symbol(&value)?;
+            Field::Symbol
+        }
+        TokenMetadataField::Uri => {
+            validate_token_uri(&value)?;
+            Field::Uri
+        }
+    };
+
+    // work out how much the metadata grows so the mint stays rent exempt
+    let mint_info = ctx.accounts.mint.to_account_info();
+    let (current_len, new_len) = {
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<MintState>::unpack(&mint_data)?;
+        let mut token_metadata = mint_state.get_variable_len_extension::<TokenMetadata>()?;
+        let old_metadata_len = token_metadata.tlv_size_of()?;
+        token_metadata.update(field.clone(), value.clone());
+        let new_metadata_len = token_metadata.tlv_size_of()?;
+        (mint_data.len(), mint_data.len() + new_metadata_len.saturating_sub(old_metadata_len))
+    };
+    if new_len > current_len {
+        top_up_rent(
+            ctx.accounts.authority.to_account_info(),
+            mint_info.clone(),
+            ctx.accounts.system_program.to_account_info(),
+            new_len,
+        )?;
+    }
+
+    let accounts = TokenMetadataUpdateField {
+        program_id: ctx.accounts.token_program.to_account_info(),
+        metadata: mint_info,
+        update_authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    let creator_token_key = ctx.accounts.creator_token.key();
+    let mint_authority_seeds: &[&[&[u8]]] = &[&[b"mint_authority", creator_token_key.as_ref(), &[ctx.accounts.creator_token.mint_authority_bump]]];
+    let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, mint_authority_seeds);
+    token_metadata_update_field(cpi_context, field, value)
+}
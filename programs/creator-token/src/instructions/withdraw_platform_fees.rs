@@ -0,0 +1,30 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+use crate::{error::{AdminError, CreatorTokenError}, Config};
+
+#[derive(Accounts)]
+pub struct WithdrawPlatformFees<'info> {
+    #[account(mut)]
+    pub admin : Signer<'info>,
+
+    #[account(seeds=[b"config"], bump=config.bump, has_one = admin @ AdminError::Unauthorized)]
+    pub config : Account<'info, Config>,
+
+    #[account(mut, seeds=[b"fee_vault"], bump=config.fee_vault_bump)]
+    pub fee_vault : SystemAccount<'info>,
+
+    pub system_program : Program<'info, System>,
+}
+
+// Sends everything above the vault's rent exemption to the admin
+pub fn handler(ctx: Context<WithdrawPlatformFees>) -> Result<()> {
+    let amount = ctx.accounts.fee_vault.lamports().saturating_sub(Rent::get()?.minimum_balance(0));
+    require!(amount > 0, CreatorTokenError::NothingToClaim);
+
+    let accounts = Transfer {
+        from: ctx.accounts.fee_vault.to_account_info(),
+        to: ctx.accounts.admin.to_account_info(),
+    };
+    let fee_vault_seeds: &[&[&[u8]]] = &[&[b"fee_vault", &[ctx.accounts.config.fee_vault_bump]]];
+    transfer(CpiContext::new_with_signer(ctx.accounts.system_program.to_account_info(), accounts, fee_vault_seeds), amount)
+}
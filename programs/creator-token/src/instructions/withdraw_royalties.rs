@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{
+        spl_token_2022::{
+            extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+            state::Mint as MintState,
+        },
+        harvest_withheld_tokens_to_mint, withdraw_withheld_tokens_from_mint, HarvestWithheldTokensToMint, Mint,
+        TokenAccount, TokenInterface, WithdrawWithheldTokensFromMint,
+    },
+};
+
+use holder_hook::program::HolderHook;
+
+use crate::{
+    constants::PERMISSION_WITHDRAW_FEES,
+    error::{CreatorTokenError, IdentityError},
+    helpers::{record_holder_mint, token_index_seed, HolderHookAccounts},
+    CreatorToken, Identity,
+};
+
+#[derive(Accounts)]
+#[instruction(token_index: u16)]
+pub struct WithdrawRoyalties<'info> {
+    // creator or a delegate with the fee withdrawal permission
+    #[account(mut)]
+    pub authority : Signer<'info>,
+
+    /// CHECK: Needed to derive proper accounts for validation
+    pub creator : AccountInfo<'info>,
+
+    #[account(
+        seeds=[b"identity", creator.key().as_ref()], 
+        bump=identity_proof.bump,
+        constraint = identity_proof.is_authorized(&authority.key(), PERMISSION_WITHDRAW_FEES) @ IdentityError::NotAuthorized
+    )]
+    pub identity_proof : Account<'info, Identity>,
+
+    #[account(
+        mut,
+        seeds=[b"creator_token", identity_proof.key().as_ref(), &token_index_seed(token_index)], 
+        bump=creator_token.bump
+    )]
+    pub creator_token : Account<'info, CreatorToken>,
+
+    #[account(
+        mut,
+        seeds = [b"owner", identity_proof.key().as_ref(), &token_index_seed(token_index)],
+        bump=creator_token.mint_bump
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // withdraw withheld authority of the transfer fee
+    #[account(seeds=[b"mint_authority", creator_token.key().as_ref()], bump=creator_token.mint_authority_bump)]
+    pub mint_authority: SystemAccount<'info>,
+
+    // Royalties always go to the creator, even when a delegate withdraws them
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program
+    )]
+    pub creator_ata : InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = token_program.key() == Token2022::id() @ CreatorTokenError::Token2022Required,
+        address = creator_token.token_program
+    )]
+    pub token_program : Interface<'info, TokenInterface>,
+
+    pub system_program : Program<'info, System>,
+
+    pub holder_hook_program: Option<Program<'info, HolderHook>>,
+
+    /// CHECK: PDA of this program the holder hook accepts supply changes from
+    #[account(seeds=[b"hook_authority"], bump)]
+    pub hook_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder record of the creator, validated by the holder hook program
+    #[account(mut)]
+    pub holder_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Holder count of the mint, validated by the holder hook program
+    #[account(mut)]
+    pub holder_stats: Option<UncheckedAccount<'info>>,
+}
+
+// `remaining_accounts` are the holder token accounts to harvest withheld fees from
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawRoyalties<'info>>, _token_index: u16) -> Result<()> {
+    if !ctx.remaining_accounts.is_empty() {
+        let accounts = HarvestWithheldTokensToMint {
+            token_program_id: ctx.accounts.token_program.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(ctx.accounts.token_program.to_account_info(), accounts);
+        harvest_withheld_tokens_to_mint(cpi_context, ctx.remaining_accounts.to_vec())?;
+    }
+
+    // amount about to land in the creator ATA, reported to the holder hook below
+    let withdrawn: u64 = {
+        let mint_info = ctx.accounts.mint.to_account_info();
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<MintState>::unpack(&mint_data)?;
+        mint_state.get_extension::<TransferFeeConfig>()?.withheld_amount.into()
+    };
+
+    let accounts = WithdrawWithheldTokensFromMint {
+        token_program_id: ctx.accounts.token_program.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        destination: ctx.accounts.creator_ata.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    let creator_token_key = ctx.accounts.creator_token.key();
+    let mint_authority_seeds: &[&[&[u8]]] = &[&[b"mint_authority", creator_token_key.as_ref(), &[ctx.accounts.creator_token.mint_authority_bump]]];
+    let cpi_context = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), accounts, mint_authority_seeds);
+    withdraw_withheld_tokens_from_mint(cpi_context)?;
+
+    // the transfer hook doesn't run on withdrawals, report the creator's new balance
+    if ctx.accounts.creator_token.has_holder_hook && withdrawn > 0 {
+        let hook = HolderHookAccounts::new(
+            ctx.accounts.holder_hook_program.as_ref().map(|program| program.to_account_info()),
+            ctx.accounts.hook_authority.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.holder_record.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.holder_stats.as_ref().map(|account| account.to_account_info()),
+            ctx.bumps.hook_authority,
+        )?;
+        ctx.accounts.creator_token.holder_count = record_holder_mint(
+            &hook,
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.creator.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            withdrawn,
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,5 +1,9 @@
+// `#[program]` still expands to the deprecated `AccountInfo::realloc`
+#![allow(deprecated)]
+
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod instructions;
 pub mod state;
 pub mod helpers;
@@ -17,41 +21,210 @@ pub mod creator_token {
 
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        initialize::handler(ctx)
+    pub fn initialize(ctx: Context<Initialize>, max_royalty_bps: u16) -> Result<()> {
+        initialize::handler(ctx, max_royalty_bps)
+    }
+
+    pub fn set_max_royalty_bps(ctx: Context<UpdateConfig>, max_royalty_bps: u16) -> Result<()> {
+        update_config::max_royalty_bps(ctx, max_royalty_bps)
+    }
+
+    pub fn set_trade_fees(ctx: Context<UpdateConfig>, trade_fee_bps: u16, referral_share_bps: u16) -> Result<()> {
+        update_config::trade_fees(ctx, trade_fee_bps, referral_share_bps)
+    }
+
+    pub fn withdraw_platform_fees(ctx: Context<WithdrawPlatformFees>) -> Result<()> {
+        withdraw_platform_fees::handler(ctx)
     }
 
     pub fn create_creator_identity(ctx: Context<CreatorIdentity>, user_name : String, proof_url: String) -> Result<()> {
         creator_identity::handler(ctx,user_name,proof_url)
     }
 
+    pub fn update_creator_identity(ctx: Context<UpdateCreatorIdentity>, user_name : String, proof_url: String) -> Result<()> {
+        update_creator_identity::handler(ctx,user_name,proof_url)
+    }
+
+    pub fn add_delegate(ctx: Context<ManageDelegates>, delegate: Pubkey, permissions: u8) -> Result<()> {
+        manage_delegates::add(ctx, delegate, permissions)
+    }
+
+    pub fn revoke_delegate(ctx: Context<ManageDelegates>, delegate: Pubkey) -> Result<()> {
+        manage_delegates::revoke(ctx, delegate)
+    }
+
+    pub fn set_buys_paused(ctx: Context<PauseBuys>, token_index: u16, paused: bool) -> Result<()> {
+        pause_buys::handler(ctx, token_index, paused)
+    }
+
+    pub fn suspend_identity(ctx: Context<ModerateIdentity>, reason: u16) -> Result<()> {
+        moderate_identity::suspend(ctx, reason)
+    }
+
+    pub fn reinstate_identity(ctx: Context<ModerateIdentity>) -> Result<()> {
+        moderate_identity::reinstate(ctx)
+    }
+
     // NOTE : We may have to remove initial supply to keep the token vault and token supply healthy
-    pub fn create_creator_token(ctx: Context<CreateCreatorToken>, 
-        decimals: u8, 
-        // initial_supply: u64, 
-        base_price: u64, 
-        slope: u64) -> Result<()> {
-        create_creator_token::handler(
-            ctx,
-            decimals, 
-            // initial_supply, 
-            base_price, 
-            slope)
+    // Each call creates the identity's next token, at index `identity_proof.token_count`
+    pub fn create_creator_token(ctx: Context<CreateCreatorToken>, params: CreateCreatorTokenParams) -> Result<()> {
+        create_creator_token::handler(ctx, params)
+    }
+
+    pub fn update_token_metadata(ctx: Context<UpdateTokenMetadata>, token_index: u16, field: TokenMetadataField, value: String) -> Result<()> {
+        update_token_metadata::handler(ctx, token_index, field, value)
+    }
+
+    pub fn migrate_mint_authority(ctx: Context<MigrateMintAuthority>, token_index: u16) -> Result<()> {
+        migrate_mint_authority::handler(ctx, token_index)
+    }
+
+    pub fn migrate_identity(ctx: Context<MigrateIdentity>) -> Result<()> {
+        migrate_account_layout::identity(ctx)
+    }
+
+    pub fn migrate_creator_token(ctx: Context<MigrateCreatorToken>) -> Result<()> {
+        migrate_account_layout::creator_token(ctx)
+    }
+
+    pub fn freeze_holder(ctx: Context<FreezeHolder>, token_index: u16, reason: u16) -> Result<()> {
+        freeze_holder::freeze(ctx, token_index, reason)
+    }
+
+    pub fn thaw_holder(ctx: Context<FreezeHolder>, token_index: u16) -> Result<()> {
+        freeze_holder::thaw(ctx, token_index)
+    }
+
+    pub fn sync_holder_count(ctx: Context<SyncHolderCount>, token_index: u16) -> Result<()> {
+        sync_holder_count::handler(ctx, token_index)
+    }
+
+    pub fn harvest_and_withdraw_royalties<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawRoyalties<'info>>, token_index: u16) -> Result<()> {
+        withdraw_royalties::handler(ctx, token_index)
+    }
+
+    pub fn claim_vested(ctx: Context<ClaimVested>, token_index: u16) -> Result<()> {
+        claim_vested::handler(ctx, token_index)
+    }
+
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        referrer::register(ctx)
+    }
+
+    pub fn claim_referral_fees(ctx: Context<ClaimReferralFees>) -> Result<()> {
+        referrer::claim(ctx)
+    }
+
+    // `expires_at` is an optional unix timestamp after which the trade is rejected
+    // `presale` is the buyer's allowlist allocation, only read before public trading opens
+    pub fn buy_creator_token(
+        ctx: Context<BuyToken>,
+        token_index: u16,
+        tokens_to_buy: u64,
+        expires_at: Option<i64>,
+        presale: Option<PresaleAllocation>,
+    ) -> Result<()> {
+        buy_token::handler(ctx, token_index, tokens_to_buy, expires_at, presale)
+    }
+
+    // The payer signs and pays, the tokens are minted to the recipient
+    pub fn gift_creator_token(
+        ctx: Context<GiftToken>,
+        token_index: u16,
+        tokens_to_buy: u64,
+        expires_at: Option<i64>,
+        presale: Option<PresaleAllocation>,
+    ) -> Result<()> {
+        gift_token::handler(ctx, token_index, tokens_to_buy, expires_at, presale)
+    }
+
+    // Buys several creator tokens atomically, each leg's accounts are passed through `remaining_accounts`
+    pub fn buy_many<'info>(ctx: Context<'_, '_, 'info, 'info, BuyMany<'info>>, legs: Vec<BuyLeg>, expires_at: Option<i64>) -> Result<()> {
+        buy_many::handler(ctx, legs, expires_at)
+    }
+
+    pub fn get_buying_token_price(ctx: Context<TokenPrice>, token_index: u16, tokens_to_buy: u64) -> Result<u64> {
+        token_price::buying_cost(ctx, token_index, tokens_to_buy)
+    }
+
+    pub fn get_selling_return_price(ctx: Context<TokenPrice>, token_index: u16, tokens_to_buy: u64) -> Result<u64> {
+        token_price::selling_return(ctx, token_index, tokens_to_buy)
+    }
+
+    pub fn sell_creator_token(ctx: Context<SellToken>, token_index: u16, token_to_sell: u64, expires_at: Option<i64>) -> Result<()> {
+        sell_token::handler(ctx, token_index, token_to_sell, expires_at)
+    }
+
+    // Sells `amount_in` of one creator token and buys another with the proceeds, vault to vault
+    pub fn swap_creator_tokens(
+        ctx: Context<SwapCreatorTokens>,
+        token_index_in: u16,
+        token_index_out: u16,
+        amount_in: u64,
+        min_amount_out: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        swap_creator_tokens::handler(ctx, token_index_in, token_index_out, amount_in, min_amount_out, expires_at)
+    }
+
+    pub fn get_swap_quote(ctx: Context<SwapQuote>, token_index_in: u16, token_index_out: u16, amount_in: u64) -> Result<SwapQuoteAmounts> {
+        swap_creator_tokens::quote(ctx, token_index_in, token_index_out, amount_in)
+    }
+
+    // `max_cost` is the lamport budget of a buy order, sell orders ignore it
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        token_index: u16,
+        order_id: u64,
+        side: LimitOrderSide,
+        amount: u64,
+        limit_price: u64,
+        max_cost: u64,
+    ) -> Result<()> {
+        limit_order::place(ctx, token_index, order_id, side, amount, limit_price, max_cost)
+    }
+
+    pub fn fill_limit_order(ctx: Context<FillLimitOrder>, token_index: u16) -> Result<()> {
+        limit_order::fill(ctx, token_index)
+    }
+
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>, token_index: u16) -> Result<()> {
+        limit_order::cancel(ctx, token_index)
+    }
+
+    pub fn create_dca_plan(
+        ctx: Context<CreateDcaPlan>,
+        token_index: u16,
+        amount_per_interval: u64,
+        interval_seconds: i64,
+        max_price: u64,
+        initial_deposit: u64,
+    ) -> Result<()> {
+        dca_plan::create(ctx, token_index, amount_per_interval, interval_seconds, max_price, initial_deposit)
+    }
+
+    pub fn top_up_dca_plan(ctx: Context<ManageDcaPlan>, amount: u64) -> Result<()> {
+        dca_plan::top_up(ctx, amount)
+    }
+
+    pub fn set_dca_plan_paused(ctx: Context<ManageDcaPlan>, paused: bool) -> Result<()> {
+        dca_plan::set_paused(ctx, paused)
     }
 
-    pub fn buy_creator_token(ctx: Context<BuyToken>, tokens_to_buy: u64 ) -> Result<()> {
-        buy_token::handler(ctx, tokens_to_buy)
+    pub fn close_dca_plan(ctx: Context<CloseDcaPlan>) -> Result<()> {
+        dca_plan::close(ctx)
     }
 
-    pub fn get_buying_token_price(ctx: Context<TokenPrice>, tokens_to_buy: u64) -> Result<u64> {
-        token_price::buying_cost(ctx, tokens_to_buy)
+    pub fn execute_dca(ctx: Context<ExecuteDca>, token_index: u16) -> Result<()> {
+        dca_plan::execute(ctx, token_index)
     }
 
-    pub fn get_selling_return_price(ctx: Context<TokenPrice>, tokens_to_buy: u64) -> Result<u64> {
-        token_price::selling_return(ctx, tokens_to_buy)
+    pub fn close_creator_token(ctx: Context<CloseCreatorToken>, token_index: u16) -> Result<()> {
+        close_creator_token::handler(ctx, token_index)
     }
 
-    pub fn sell_creator_token(ctx: Context<SellToken>, token_to_sell: u64 ) -> Result<()> {
-        sell_token::handler(ctx, token_to_sell)
+    pub fn close_creator_identity(ctx: Context<CloseCreatorIdentity>) -> Result<()> {
+        close_creator_identity::handler(ctx)
     }
 }
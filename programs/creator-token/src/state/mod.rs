@@ -1,5 +1,48 @@
 use anchor_lang::prelude::*;
 
+use crate::{
+    constants::{MAX_DELEGATES, REGISTRY_PAGE_SIZE},
+    error::{CreatorTokenError, CustomError},
+};
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    // Creator tokens ever created, also the position of the next one in the registry
+    pub creator_token_count: u64,
+    // Upper bound for the transfer fee creators can charge on secondary transfers
+    pub max_royalty_bps: u16,
+    // Token-2022 group mint every Token-2022 creator mint is a member of
+    pub group_mint: Pubkey,
+    pub group_mint_bump: u8,
+    // Fee on every curve trade, kept in the fee vault minus the referrer's share
+    pub trade_fee_bps: u16,
+    // Share of the trading fee paid to the referrer of a trade, if there is one
+    pub referral_share_bps: u16,
+    pub fee_vault_bump: u8,
+    pub bump: u8,
+}
+
+// Page `page_index` of the creator token registry, holds the tokens created
+// between `page_index * REGISTRY_PAGE_SIZE` and `(page_index + 1) * REGISTRY_PAGE_SIZE`.
+// Entries stay after a token is closed, clients check that the mint still exists.
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryPage {
+    pub page_index: u64,
+    #[max_len(REGISTRY_PAGE_SIZE)]
+    pub entries: Vec<RegistryEntry>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RegistryEntry {
+    pub mint: Pubkey,
+    pub identity: Pubkey,
+    pub created_at: i64,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct Identity{
@@ -11,7 +54,66 @@ pub struct Identity{
     #[max_len(200)]
     pub proof_url: String,
 
-    pub bump : u8
+    pub bump : u8,
+
+    // Fields below were added after launch, accounts created before them are grown by migrate_identity
+
+    // Moderation, set by the admin through suspend_identity / reinstate_identity
+    pub status : IdentityStatus,
+    pub suspension_reason : u16,
+
+    // Index the next creator token will be created with, carried over to a new identity by TokenCounter
+    pub token_count : u16,
+    // Creator tokens that have not been closed yet
+    pub active_tokens : u16,
+
+    // Operator keys allowed to act for the creator, scoped by permission flags
+    #[max_len(MAX_DELEGATES)]
+    pub delegates : Vec<Delegate>,
+}
+
+impl Identity {
+    // The creator can do everything, a delegate only what its permissions allow
+    pub fn is_authorized(&self, signer: &Pubkey, permission: u8) -> bool {
+        *signer == self.creator
+            || self
+                .delegates
+                .iter()
+                .any(|delegate| delegate.key == *signer && delegate.permissions & permission == permission)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Delegate {
+    pub key : Pubkey,
+    pub permissions : u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum IdentityStatus {
+    Active,
+    // Only sells are allowed so holders can still exit at curve price
+    Suspended,
+}
+
+// Written when a creator closes their identity and read back when they create a new one,
+// so token indices, and every PDA seeded by them, are never handed out twice
+#[account]
+#[derive(InitSpace)]
+pub struct TokenCounter {
+    pub creator: Pubkey,
+    pub token_count: u16,
+    pub bump: u8,
+}
+
+// Referral earnings of `authority`, the unclaimed part is held as lamports on this account
+#[account]
+#[derive(InitSpace)]
+pub struct Referrer {
+    pub authority: Pubkey,
+    pub total_earned: u64,
+    pub total_claimed: u64,
+    pub bump: u8,
 }
 
 // Slope TBD
@@ -29,4 +131,181 @@ pub struct CreatorToken {
     pub mint_authority_bump: u8,
     pub vault_bump: u8,
     pub mint_bump: u8,
+    // Fields below were added after launch, accounts created before them are grown by migrate_creator_token
+    pub token_index: u16,
+    pub buys_paused: bool,
+    // Token-2022 transfer fee paid to the creator on transfers outside the curve, 0 if disabled
+    pub royalty_bps: u16,
+    // Mint registered with the holder hook program, curve trades report to it
+    pub has_holder_hook: bool,
+    // Soulbound mint, wallet-to-wallet transfers are rejected by Token-2022
+    pub non_transferable: bool,
+    // Tokens reserved for the creator at creation, minted into the vesting escrow. Not backed by the vault
+    pub creator_allocation: u64,
+    // SPL Token or Token-2022, whichever owns the mint
+    pub token_program: Pubkey,
+    // First `early_holder_limit` distinct buyers get a badge NFT, `early_holder_count` have one so far
+    pub early_holder_limit: u16,
+    pub early_holder_count: u16,
+    // Tokens burned into open sell limit orders, the curve still counts them until the orders fill
+    pub sell_order_escrow: u64,
+    // Limit orders not filled or cancelled yet, the token can't be closed while any are open
+    pub open_limit_orders: u32,
+    // Buys open at `trading_starts_at`. Until `anti_snipe_ends_at` buy_creator_token and gift_creator_token
+    // are the only way in and cap each buy and each wallet's total at the limits below, 0 for no cap
+    pub trading_starts_at: i64,
+    pub anti_snipe_ends_at: i64,
+    pub anti_snipe_max_buy: u64,
+    pub anti_snipe_max_wallet: u64,
+    // Merkle root of the presale allowlist, only its wallets can buy before `trading_starts_at`.
+    // All zeros when the token has no presale
+    pub presale_merkle_root: [u8; 32],
+    // Sell tax of `sell_tax_bps` on tokens bought right before the sale, decaying linearly to 0
+    // over `sell_tax_decay_seconds`. The tax stays in the vault or goes to the creator
+    pub sell_tax_bps: u16,
+    pub sell_tax_decay_seconds: i64,
+    pub sell_tax_to_creator: bool,
+    // Holder count of the holder hook, refreshed by curve trades and by sync_holder_count
+    // after wallet-to-wallet transfers
+    pub holder_count: u64,
+}
+
+impl CreatorToken {
+    // Supply the curve prices against, sell order escrow is burned but not sold yet. The creator
+    // allocation was never paid into the vault, leaving it out caps every sell at what buyers own.
+    // Saturates so burning claimed allocation tokens can't lock the curve
+    pub fn curve_supply(&self, mint_supply: u64) -> Result<u64> {
+        mint_supply
+            .checked_add(self.sell_order_escrow)
+            .map(|supply| supply.saturating_sub(self.creator_allocation))
+            .ok_or(error!(CustomError::MathOverflow))
+    }
+
+    pub fn has_presale(&self) -> bool {
+        self.presale_merkle_root != [0; 32]
+    }
+
+    pub fn has_sell_tax(&self) -> bool {
+        self.sell_tax_bps > 0
+    }
+
+    // For every buy route except buy_creator_token and gift_creator_token, which enforce the launch limits themselves
+    pub fn check_open_for_buys(&self, now: i64) -> Result<()> {
+        require!(now >= self.trading_starts_at, CreatorTokenError::TradingNotStarted);
+        require!(now >= self.anti_snipe_ends_at, CreatorTokenError::AntiSnipeWindowActive);
+        // only buy_creator_token and gift_creator_token record when tokens were bought
+        self.check_untaxed()
+    }
+
+    // Sell taxed tokens only trade through buy_creator_token, gift_creator_token and sell_creator_token
+    pub fn check_untaxed(&self) -> Result<()> {
+        require!(!self.has_sell_tax(), CreatorTokenError::SellTaxedToken);
+        Ok(())
+    }
+}
+
+// Tokens a wallet bought with buy_creator_token and when, on average weighted by amount. Sells of a
+// token with a sell tax draw it down and are taxed by its age, it never counts more than the wallet holds
+#[account]
+#[derive(InitSpace)]
+pub struct HolderPosition {
+    pub creator_token: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub acquired_at: i64,
+    pub bump: u8,
+}
+
+impl HolderPosition {
+    pub fn record_buy(&mut self, amount: u64, now: i64) -> Result<()> {
+        let total = self.amount.checked_add(amount).ok_or(error!(CustomError::MathOverflow))?;
+        let weighted = (self.acquired_at as i128) * (self.amount as i128) + (now as i128) * (amount as i128);
+        self.acquired_at = i64::try_from(weighted / (total.max(1) as i128)).map_err(|_| error!(CustomError::MathOverflow))?;
+        self.amount = total;
+        Ok(())
+    }
+}
+
+// Base units a wallet got from buy_creator_token or gift_creator_token in the presale and during the anti-snipe window
+#[account]
+#[derive(InitSpace)]
+pub struct LaunchBuys {
+    pub creator_token: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub presale_amount: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum LimitOrderSide {
+    Buy,
+    Sell,
+}
+
+// Buy orders hold their lamport budget on this account, sell orders burn their tokens into
+// `CreatorToken::sell_order_escrow`. Both hold the keeper reward until filled or cancelled.
+#[account]
+#[derive(InitSpace)]
+pub struct LimitOrder {
+    pub owner: Pubkey,
+    pub creator_token: Pubkey,
+    pub order_id: u64,
+    pub side: LimitOrderSide,
+    pub amount: u64,
+    // Marginal curve price the order fills at or better, lamports per whole token
+    pub limit_price: u64,
+    // Buy orders only, most lamports the fill may spend with the trading fee
+    pub max_cost: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+// Recurring curve buy of a fan, `balance` is the SOL deposited on this account
+// that executions draw from, on top of its rent
+#[account]
+#[derive(InitSpace)]
+pub struct DcaPlan {
+    pub owner: Pubkey,
+    pub creator_token: Pubkey,
+    pub balance: u64,
+    // Lamports spent per execution, trading fee included
+    pub amount_per_interval: u64,
+    pub interval_seconds: i64,
+    // Executions are skipped while the marginal curve price is above this, lamports per whole token
+    pub max_price: u64,
+    pub next_execution_at: i64,
+    pub paused: bool,
+    pub total_spent: u64,
+    pub total_bought: u64,
+    pub bump: u8,
+}
+
+// Creator allocation held in escrow, unlocks linearly from `start_ts` to `end_ts`
+// with nothing claimable before `cliff_ts`
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorVesting {
+    pub creator_token: Pubkey,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub start_ts: i64,
+    pub cliff_ts: i64,
+    pub end_ts: i64,
+    pub bump: u8,
+}
+
+impl CreatorVesting {
+    pub fn vested_amount(&self, now: i64) -> Option<u64> {
+        if now < self.cliff_ts {
+            return Some(0);
+        }
+        if now >= self.end_ts {
+            return Some(self.total_amount);
+        }
+        let elapsed = u128::try_from(now.checked_sub(self.start_ts)?).ok()?;
+        let duration = u128::try_from(self.end_ts.checked_sub(self.start_ts)?).ok()?;
+        let vested = (self.total_amount as u128).checked_mul(elapsed)?.checked_div(duration)?;
+        u64::try_from(vested).ok()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,6 @@
+use anchor_lang::prelude::*;
+
+// The only program allowed to report curve mints and burns, through its
+// `[b"hook_authority"]` PDA
+#[constant]
+pub const CREATOR_TOKEN_PROGRAM_ID: Pubkey = pubkey!("EEiGYjpth5GS6fNvX2a2ZbQV7gCNAZjMRE3XMtBrFbfq");
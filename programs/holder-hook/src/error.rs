@@ -0,0 +1,10 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum HolderHookError {
+    #[msg("The hook can only run while the token is being transferred")]
+    NotTransferring,
+
+    #[msg("Overflow occured while calculating")]
+    MathOverflow,
+}
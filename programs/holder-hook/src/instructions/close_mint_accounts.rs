@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::CREATOR_TOKEN_PROGRAM_ID, HolderStats};
+
+// Creator token closed, its mint's validation account and holder count go back to `destination`
+// so the same mint address can be registered again. Holder records belong to their payers and stay.
+#[derive(Accounts)]
+pub struct CloseMintAccounts<'info> {
+    #[account(seeds = [b"hook_authority"], bump, seeds::program = CREATOR_TOKEN_PROGRAM_ID)]
+    pub hook_authority : Signer<'info>,
+
+    /// CHECK: Receives the rent
+    #[account(mut)]
+    pub destination : UncheckedAccount<'info>,
+
+    /// CHECK: Only its address is used, the mint may be closed in the same transaction
+    pub mint : UncheckedAccount<'info>,
+
+    /// CHECK: Laid out by `ExtraAccountMetaList::init`, closed in the handler
+    #[account(mut, owner = crate::ID, seeds = [b"extra-account-metas", mint.key().as_ref()], bump)]
+    pub extra_account_meta_list : UncheckedAccount<'info>,
+
+    #[account(mut, close = destination, seeds = [b"holder_stats", mint.key().as_ref()], bump = holder_stats.bump)]
+    pub holder_stats : Account<'info, HolderStats>,
+}
+
+pub fn handler(ctx: Context<CloseMintAccounts>) -> Result<()> {
+    // the same steps Anchor's `close` takes, the list isn't an Anchor account
+    let extra_account_meta_list = ctx.accounts.extra_account_meta_list.to_account_info();
+    let lamports = extra_account_meta_list.lamports();
+    ctx.accounts.destination.add_lamports(lamports)?;
+    extra_account_meta_list.sub_lamports(lamports)?;
+    extra_account_meta_list.assign(&System::id());
+    extra_account_meta_list.resize(0)?;
+
+    // holder_stats is closed by the `close` constraint
+    Ok(())
+}
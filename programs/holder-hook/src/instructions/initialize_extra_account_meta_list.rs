@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+use crate::HolderStats;
+
+// Accounts Token-2022 appends to every Execute call, after
+// source (0), mint (1), destination (2), owner (3) and this list (4)
+pub fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    // owner field of a token account
+    let owner_of = |account_index: u8| Seed::AccountData { account_index, data_index: 32, length: 32 };
+
+    Ok(vec![
+        // holder_stats (5)
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"holder_stats".to_vec() }, Seed::AccountKey { index: 1 }],
+            false,
+            true,
+        )?,
+        // source_record (6)
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"holder".to_vec() }, Seed::AccountKey { index: 1 }, owner_of(0)],
+            false,
+            true,
+        )?,
+        // destination_record (7)
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"holder".to_vec() }, Seed::AccountKey { index: 1 }, owner_of(2)],
+            false,
+            true,
+        )?,
+    ])
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer : Signer<'info>,
+
+    /// CHECK: Laid out by `ExtraAccountMetaList::init` in the handler
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        space = ExtraAccountMetaList::size_of(extra_account_metas()?.len())?,
+        bump
+    )]
+    pub extra_account_meta_list : UncheckedAccount<'info>,
+
+    pub mint : InterfaceAccount<'info, Mint>,
+
+    #[account(init, payer = payer, seeds = [b"holder_stats", mint.key().as_ref()], space = 8 + HolderStats::INIT_SPACE, bump)]
+    pub holder_stats : Account<'info, HolderStats>,
+
+    pub system_program : Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<InitializeExtraAccountMetaList>) -> Result<()> {
+    let mut data = ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?;
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_account_metas()?)?;
+
+    ctx.accounts.holder_stats.mint = ctx.accounts.mint.key();
+    ctx.accounts.holder_stats.holder_count = 0;
+    ctx.accounts.holder_stats.bump = ctx.bumps.holder_stats;
+
+    Ok(())
+}
@@ -0,0 +1,13 @@
+#![allow(ambiguous_glob_reexports)]
+
+pub mod initialize_extra_account_meta_list;
+pub mod open_holder_record;
+pub mod transfer_hook;
+pub mod record_supply_change;
+pub mod close_mint_accounts;
+
+pub use initialize_extra_account_meta_list::*;
+pub use open_holder_record::*;
+pub use transfer_hook::*;
+pub use record_supply_change::*;
+pub use close_mint_accounts::*;
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::HolderRecord;
+
+// The hook can't create accounts, so transfers to an owner without a record aren't
+// tracked. Anyone can pay for one, clients prepend this to transfers to new holders
+// so they are counted.
+#[derive(Accounts)]
+pub struct OpenHolderRecord<'info> {
+    #[account(mut)]
+    pub payer : Signer<'info>,
+
+    pub mint : InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Any wallet or PDA that can own token accounts
+    pub owner : UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"holder", mint.key().as_ref(), owner.key().as_ref()],
+        space = 8 + HolderRecord::INIT_SPACE,
+        bump
+    )]
+    pub holder_record : Account<'info, HolderRecord>,
+
+    pub system_program : Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<OpenHolderRecord>) -> Result<()> {
+    let holder_record = &mut ctx.accounts.holder_record;
+    holder_record.mint = ctx.accounts.mint.key();
+    holder_record.owner = ctx.accounts.owner.key();
+    holder_record.balance = 0;
+    holder_record.first_acquired_at = 0;
+    holder_record.bump = ctx.bumps.holder_record;
+
+    Ok(())
+}
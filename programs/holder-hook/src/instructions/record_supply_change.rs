@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{constants::CREATOR_TOKEN_PROGRAM_ID, error::HolderHookError, HolderRecord, HolderStats};
+
+// Curve buy, the buyer pays for its record the first time
+#[derive(Accounts)]
+pub struct RecordMint<'info> {
+    #[account(seeds = [b"hook_authority"], bump, seeds::program = CREATOR_TOKEN_PROGRAM_ID)]
+    pub hook_authority : Signer<'info>,
+
+    #[account(mut)]
+    pub payer : Signer<'info>,
+
+    pub mint : InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Owner of the token account the tokens were minted to
+    pub owner : UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"holder", mint.key().as_ref(), owner.key().as_ref()],
+        space = 8 + HolderRecord::INIT_SPACE,
+        bump
+    )]
+    pub holder_record : Account<'info, HolderRecord>,
+
+    #[account(mut, seeds = [b"holder_stats", mint.key().as_ref()], bump = holder_stats.bump)]
+    pub holder_stats : Account<'info, HolderStats>,
+
+    pub system_program : Program<'info, System>,
+}
+
+// Curve sell
+#[derive(Accounts)]
+pub struct RecordBurn<'info> {
+    #[account(seeds = [b"hook_authority"], bump, seeds::program = CREATOR_TOKEN_PROGRAM_ID)]
+    pub hook_authority : Signer<'info>,
+
+    pub mint : InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Owner of the token account the tokens were burned from
+    pub owner : UncheckedAccount<'info>,
+
+    /// CHECK: HolderRecord of the owner, if it has one. Tokens it only received by transfer
+    /// before opening one were never tracked
+    #[account(mut, seeds = [b"holder", mint.key().as_ref(), owner.key().as_ref()], bump)]
+    pub holder_record : UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"holder_stats", mint.key().as_ref()], bump = holder_stats.bump)]
+    pub holder_stats : Account<'info, HolderStats>,
+}
+
+pub fn minted(ctx: Context<RecordMint>, amount: u64) -> Result<()> {
+    let holder_record = &mut ctx.accounts.holder_record;
+    if holder_record.mint == Pubkey::default() {
+        holder_record.mint = ctx.accounts.mint.key();
+        holder_record.owner = ctx.accounts.owner.key();
+        holder_record.bump = ctx.bumps.holder_record;
+    }
+
+    let became_holder = holder_record
+        .credit(amount, Clock::get()?.unix_timestamp)
+        .ok_or(error!(HolderHookError::MathOverflow))?;
+    ctx.accounts
+        .holder_stats
+        .apply(became_holder, false)
+        .ok_or(error!(HolderHookError::MathOverflow))?;
+
+    Ok(())
+}
+
+pub fn burned(ctx: Context<RecordBurn>, amount: u64) -> Result<()> {
+    let Some(mut holder_record) = HolderRecord::load(&ctx.accounts.holder_record)? else {
+        return Ok(());
+    };
+    let stopped_holding = holder_record.debit(amount);
+    holder_record.store(&ctx.accounts.holder_record)?;
+
+    ctx.accounts
+        .holder_stats
+        .apply(false, stopped_holding)
+        .ok_or(error!(HolderHookError::MathOverflow))?;
+
+    Ok(())
+}
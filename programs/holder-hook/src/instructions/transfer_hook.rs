@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    spl_token_2022::{
+        extension::{
+            transfer_fee::TransferFeeConfig, transfer_hook::TransferHookAccount, BaseStateWithExtensions,
+            StateWithExtensions,
+        },
+        state::{Account as TokenAccountState, Mint as MintState},
+    },
+    Mint, TokenAccount,
+};
+
+use crate::{error::HolderHookError, HolderRecord, HolderStats};
+
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token : InterfaceAccount<'info, TokenAccount>,
+
+    pub mint : InterfaceAccount<'info, Mint>,
+
+    #[account(token::mint = mint)]
+    pub destination_token : InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Owner or delegate of the source account, checked by Token-2022
+    pub owner : UncheckedAccount<'info>,
+
+    /// CHECK: Validation account read by Token-2022
+    #[account(seeds = [b"extra-account-metas", mint.key().as_ref()], bump)]
+    pub extra_account_meta_list : UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"holder_stats", mint.key().as_ref()], bump = holder_stats.bump)]
+    pub holder_stats : Account<'info, HolderStats>,
+
+    /// CHECK: HolderRecord of the source owner, if it has one
+    #[account(mut, seeds = [b"holder", mint.key().as_ref(), source_token.owner.as_ref()], bump)]
+    pub source_record : UncheckedAccount<'info>,
+
+    /// CHECK: HolderRecord of the destination owner, if it has one. The hook can't create it,
+    /// transfers to owners without a record go through untracked
+    #[account(mut, seeds = [b"holder", mint.key().as_ref(), destination_token.owner.as_ref()], bump)]
+    pub destination_record : UncheckedAccount<'info>,
+}
+
+pub fn handler(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+    // only Token-2022 in the middle of a transfer may call this
+    {
+        let source_info = ctx.accounts.source_token.to_account_info();
+        let source_data = source_info.try_borrow_data()?;
+        let source_state = StateWithExtensions::<TokenAccountState>::unpack(&source_data)?;
+        let transferring: bool = source_state.get_extension::<TransferHookAccount>()?.transferring.into();
+        require!(transferring, HolderHookError::NotTransferring);
+    }
+
+    // moving tokens between accounts of the same owner changes nothing
+    if ctx.accounts.source_record.key() == ctx.accounts.destination_record.key() {
+        return Ok(());
+    }
+
+    // the destination only receives what is left after the transfer fee
+    let fee = {
+        let mint_info = ctx.accounts.mint.to_account_info();
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<MintState>::unpack(&mint_data)?;
+        match mint_state.get_extension::<TransferFeeConfig>() {
+            Ok(transfer_fee_config) => transfer_fee_config
+                .calculate_epoch_fee(Clock::get()?.epoch, amount)
+                .ok_or(error!(HolderHookError::MathOverflow))?,
+            Err(_) => 0,
+        }
+    };
+    let received = amount.checked_sub(fee).ok_or(error!(HolderHookError::MathOverflow))?;
+
+    let mut stopped_holding = false;
+    if let Some(mut source_record) = HolderRecord::load(&ctx.accounts.source_record)? {
+        stopped_holding = source_record.debit(amount);
+        source_record.store(&ctx.accounts.source_record)?;
+    }
+
+    let mut became_holder = false;
+    if let Some(mut destination_record) = HolderRecord::load(&ctx.accounts.destination_record)? {
+        became_holder = destination_record
+            .credit(received, Clock::get()?.unix_timestamp)
+            .ok_or(error!(HolderHookError::MathOverflow))?;
+        destination_record.store(&ctx.accounts.destination_record)?;
+    }
+
+    ctx.accounts
+        .holder_stats
+        .apply(became_holder, stopped_holding)
+        .ok_or(error!(HolderHookError::MathOverflow))?;
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+// `#[program]` still expands to the deprecated `AccountInfo::realloc`
+#![allow(deprecated)]
+
+pub mod constants;
+pub mod error;
+pub mod instructions;
+pub mod state;
+
+use anchor_lang::prelude::*;
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+use spl_discriminator::SplDiscriminate;
+
+pub use constants::*;
+pub use instructions::*;
+pub use state::*;
+
+declare_id!("BrqgrtweUgRuQZW36rtHPgJhHCsMstJANVe6BAJkPZnG");
+
+// Transfer hook registered on creator mints, keeps a record per holder and the
+// holder count per mint up to date on every Token-2022 transfer. Curve buys and
+// sells don't go through transfers, the creator-token program reports them with
+// record_mint / record_burn.
+#[program]
+pub mod holder_hook {
+
+    use super::*;
+
+    pub fn initialize_extra_account_meta_list(ctx: Context<InitializeExtraAccountMetaList>) -> Result<()> {
+        initialize_extra_account_meta_list::handler(ctx)
+    }
+
+    pub fn open_holder_record(ctx: Context<OpenHolderRecord>) -> Result<()> {
+        open_holder_record::handler(ctx)
+    }
+
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        transfer_hook::handler(ctx, amount)
+    }
+
+    pub fn record_mint(ctx: Context<RecordMint>, amount: u64) -> Result<()> {
+        record_supply_change::minted(ctx, amount)
+    }
+
+    pub fn record_burn(ctx: Context<RecordBurn>, amount: u64) -> Result<()> {
+        record_supply_change::burned(ctx, amount)
+    }
+
+    pub fn close_mint_accounts(ctx: Context<CloseMintAccounts>) -> Result<()> {
+        close_mint_accounts::handler(ctx)
+    }
+}
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct HolderStats {
+    pub mint: Pubkey,
+    // Owners with a non zero balance
+    pub holder_count: u64,
+    pub bump: u8,
+}
+
+// Holdings of one owner across its token accounts of a mint
+#[account]
+#[derive(InitSpace)]
+pub struct HolderRecord {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub balance: u64,
+    // When the owner first got any of the token, 0 until then
+    pub first_acquired_at: i64,
+    pub bump: u8,
+}
+
+impl HolderRecord {
+    // Adds to the balance, returns true when the owner just became a holder
+    pub fn credit(&mut self, amount: u64, now: i64) -> Option<bool> {
+        let was_holder = self.balance > 0;
+        self.balance = self.balance.checked_add(amount)?;
+        if self.first_acquired_at == 0 && self.balance > 0 {
+            self.first_acquired_at = now;
+        }
+        Some(!was_holder && self.balance > 0)
+    }
+
+    // Takes from the balance, returns true when the owner just stopped being a holder. Tokens
+    // received before the record was opened were never credited, so the balance stops at 0
+    pub fn debit(&mut self, amount: u64) -> bool {
+        let was_holder = self.balance > 0;
+        self.balance = self.balance.saturating_sub(amount);
+        was_holder && self.balance == 0
+    }
+
+    // Records are optional in transfers and curve burns, owners without one aren't tracked.
+    // Callers check the address, an account there is either a record or was never created
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+        Ok(Some(Self::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+    }
+
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        self.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])
+    }
+}
+
+impl HolderStats {
+    pub fn apply(&mut self, became_holder: bool, stopped_holding: bool) -> Option<()> {
+        if became_holder {
+            self.holder_count = self.holder_count.checked_add(1)?;
+        }
+        if stopped_holding {
+            self.holder_count = self.holder_count.checked_sub(1)?;
+        }
+        Some(())
+    }
+}
@@ -1,16 +1,25 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { CreatorToken } from "../target/types/creator_token";
+import { HolderHook } from "../target/types/holder_hook";
 import { assert, expect } from "chai";
 import {
   ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccount,
   getAssociatedTokenAddress,
   getAssociatedTokenAddressSync,
   getMint,
+  getTokenGroupMemberState,
+  getTokenGroupState,
+  getTokenMetadata,
   Mint,
   TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  transferChecked,
+  transferCheckedWithFeeAndTransferHook,
+  transferCheckedWithTransferHook,
 } from "@solana/spl-token";
-import { airDropSOLAmount, buyCreatorToken, checkConfirmTransaction, getBuyingPriceForToken, getSellingPriceForToken } from "./helper-fns";
+import { airDropSOLAmount, buildPresaleAllowlist, buyCreatorToken, getBuyLegAccounts, checkConfirmTransaction, createCreatorToken, getBadgeAccounts, getDcaPlanAddress, getLaunchBuysAddress, getBuyingPriceForToken, getCreatorTokenAccounts, getExtraAccountMetaListAddress, getHolderHookAccounts, getHolderPositionAddress, getLimitOrderAddress, getPlatformGroupAddress, getProgramDataAddress, getReferrerAddress, getRegistryPageAddress, getSellingPriceForToken, getVestingAccounts } from "./helper-fns";
 
 describe("creator-token", () => {
   // Configure the client to use the local cluster.
@@ -18,9 +27,13 @@ describe("creator-token", () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.creatorToken as Program<CreatorToken>;
+  const holderHookProgram = anchor.workspace.holderHook as Program<HolderHook>;
   const creator = anchor.web3.Keypair.generate();
   const fan = anchor.web3.Keypair.generate();
 
+  // Platform config
+  const MAX_ROYALTY_BPS = 500;
+
   // Token details
   let creatorToken: Mint;
   let identityAddress: anchor.web3.PublicKey;
@@ -35,7 +48,17 @@ describe("creator-token", () => {
 
   it("Is initialized!", async () => {
     // Add your test here.
-    const tx = await program.methods.initialize().rpc();
+    // lamports sent to the group mint address ahead of time don't block the setup
+    await airDropSOLAmount(provider, getPlatformGroupAddress(program.programId), 0.001);
+
+    const tx = await program.methods
+      .initialize(MAX_ROYALTY_BPS)
+      .accountsPartial({
+        admin: provider.wallet.publicKey,
+        program: program.programId,
+        programData: getProgramDataAddress(program.programId),
+      })
+      .rpc();
     console.log("Your transaction signature", tx);
   });
 
@@ -135,16 +158,37 @@ describe("creator-token", () => {
     // );
     const basePrice = new anchor.BN(5_000_000);
     const slope = new anchor.BN(700_000);
+    const tokenName = "Jonathan Joestar";
+    const tokenSymbol = "JOJO";
+    const tokenUri = "https://proof_url.com/token.json";
     // Call creator token
-    const tx = await program.methods
-      // .createCreatorToken(tokenDecimals, initialSupply, basePrice, slope)
-      .createCreatorToken(tokenDecimals, basePrice, slope)
-      .accounts({
-        creator: creator.publicKey,
-        tokenProgram: TOKEN_2022_PROGRAM_ID,
-      })
-      .signers([creator])
-      .rpc();
+    const { createCreatorTokenTx: tx } = await createCreatorToken({
+      provider,
+      program,
+      signer: creator,
+      params: {
+        decimals: tokenDecimals,
+        basePrice,
+        slope,
+        name: tokenName,
+        symbol: tokenSymbol,
+        uri: tokenUri,
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
 
     console.log("Checking success token creators :", tx);
 
@@ -165,7 +209,11 @@ describe("creator-token", () => {
       TOKEN_2022_PROGRAM_ID
     );
 
-    const mintAuthoritySeed = [Buffer.from("mint_authority")];
+    // Every creator token has its own mint authority
+    const mintAuthoritySeed = [
+      Buffer.from("mint_authority"),
+      getCreatorTokenAccounts(program.programId, creator.publicKey).creatorToken.toBuffer(),
+    ];
     const [mintAuthority, _mintAuthBump] =
       await anchor.web3.PublicKey.findProgramAddressSync(
         mintAuthoritySeed,
@@ -179,6 +227,17 @@ describe("creator-token", () => {
     expect(creatorToken.decimals).eq(tokenDecimals);
     expect(creatorToken.isInitialized).eq(true);
 
+    const tokenMetadata = await getTokenMetadata(
+      provider.connection,
+      tokenMintAddress,
+      "confirmed",
+      TOKEN_2022_PROGRAM_ID
+    );
+    expect(tokenMetadata.name).eq(tokenName);
+    expect(tokenMetadata.symbol).eq(tokenSymbol);
+    expect(tokenMetadata.uri).eq(tokenUri);
+    expect(tokenMetadata.updateAuthority.toBase58()).eq(mintAuthority.toBase58());
+
     // Check if initialSupply was supplied to creator's ATA
     // const creatorsATA = getAssociatedTokenAddressSync(
     //   creatorToken.address,
@@ -269,9 +328,10 @@ describe("creator-token", () => {
     expect(fanATABalance.value.amount).eq(tokenToBuy.toString());
 
     const lamportsNeeded = await program.methods
-      .getSellingReturnPrice(tokenToBuy)
-      .accounts({
+      .getSellingReturnPrice(0, tokenToBuy)
+      .accountsPartial({
         creator: creator.publicKey,
+        ...getCreatorTokenAccounts(program.programId, creator.publicKey),
       })
       .view();
     // console.log("CHECKING lamports needed ", new anchor.BN(lamportsNeeded).toString());
@@ -309,11 +369,13 @@ describe("creator-token", () => {
     expect(vaultBalanceBefore).greaterThan(0);
 
     const sellTx = await program.methods
-      .sellCreatorToken(tokenToSell)
-      .accounts({
+      .sellCreatorToken(0, tokenToSell, null)
+      .accountsPartial({
         seller: fan.publicKey,
         creator: creator.publicKey,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, creator.publicKey),
+        ...getHolderHookAccounts(program.programId, creatorToken.address, fan.publicKey),
       })
       .signers([fan])
       .rpc();
@@ -393,11 +455,13 @@ describe("creator-token", () => {
     // fan1 sells creator token first to get a profit
     // fan2 sells creator token second at a loss
     const fan1SellTx = await program.methods
-      .sellCreatorToken(tokenAmtToSell)
-      .accounts({
+      .sellCreatorToken(0, tokenAmtToSell, null)
+      .accountsPartial({
         seller: fan1.publicKey,
         creator: creator.publicKey,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, creator.publicKey),
+        ...getHolderHookAccounts(program.programId, creatorToken.address, fan1.publicKey),
       })
       .signers([fan1])
       .rpc();
@@ -409,11 +473,13 @@ describe("creator-token", () => {
     const fan2QuotedSellPrice = await getSellingPriceForToken(program, amtOfTokens, creatorToken.decimals, creator.publicKey);
 
     const fan2SellTx = await program.methods
-      .sellCreatorToken(tokenAmtToSell)
-      .accounts({
+      .sellCreatorToken(0, tokenAmtToSell, null)
+      .accountsPartial({
         seller: fan2.publicKey,
         creator: creator.publicKey,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, creator.publicKey),
+        ...getHolderHookAccounts(program.programId, creatorToken.address, fan2.publicKey),
       })
       .signers([fan2])
       .rpc();
@@ -432,4 +498,2390 @@ describe("creator-token", () => {
     expect(fan2QuotedBuyingPrice.toString()).eq(fan1QuotedSellPrice.toString());
     expect(fan1QuotedBuyingPrice.toString()).eq(fan2QuotedSellPrice.toString());
   });
-});
\ No newline at end of file
+
+  it("Fails closing a creator token or identity while tokens are in circulation", async () => {
+    try {
+      const tx = await program.methods
+        .closeCreatorToken(0)
+        .accountsPartial({
+          creator: creator.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          ...getCreatorTokenAccounts(program.programId, creator.publicKey),
+          vesting: null,
+          vestingEscrow: null,
+        })
+        .signers([creator])
+        .rpc();
+
+      assert.fail(
+        `Expected transaction to fail but it succeeded instead :${tx}`
+      );
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("SupplyNotZero");
+    }
+
+    try {
+      const tx = await program.methods
+        .closeCreatorIdentity()
+        .accounts({ creator: creator.publicKey })
+        .signers([creator])
+        .rpc();
+
+      assert.fail(
+        `Expected transaction to fail but it succeeded instead :${tx}`
+      );
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("CreatorTokenStillExists");
+    }
+  });
+
+  it("Success closing an unused creator token and identity", async () => {
+    const abandonedCreator = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, abandonedCreator.publicKey, 5);
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Dio Brando", "https://proof_url.com/")
+      .accounts({ creator: abandonedCreator.publicKey })
+      .signers([abandonedCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+
+    await createCreatorToken({
+      provider,
+      program,
+      signer: abandonedCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(5_000_000),
+        slope: new anchor.BN(700_000),
+        name: "Dio Coin",
+        symbol: "DIO",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+
+    const [abandonedIdentity] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("identity"), abandonedCreator.publicKey.toBuffer()],
+      program.programId
+    );
+    const [abandonedMint] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("owner"), abandonedIdentity.toBuffer()],
+      program.programId
+    );
+
+    const { holderHookProgram: hookProgramId, hookAuthority, holderStats } = getHolderHookAccounts(
+      program.programId,
+      abandonedMint,
+      abandonedCreator.publicKey
+    );
+    const extraAccountMetaList = getExtraAccountMetaListAddress(abandonedMint);
+    const closeTokenTx = await program.methods
+      .closeCreatorToken(0)
+      .accountsPartial({
+        creator: abandonedCreator.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, abandonedCreator.publicKey),
+        vesting: null,
+        vestingEscrow: null,
+        holderHookProgram: hookProgramId,
+        hookAuthority,
+        extraAccountMetaList,
+        holderStats,
+      })
+      .signers([abandonedCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, closeTokenTx);
+    expect(await provider.connection.getAccountInfo(extraAccountMetaList, "confirmed")).eq(null);
+    expect(await provider.connection.getAccountInfo(holderStats, "confirmed")).eq(null);
+
+    const closeIdentityTx = await program.methods
+      .closeCreatorIdentity()
+      .accounts({ creator: abandonedCreator.publicKey })
+      .signers([abandonedCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, closeIdentityTx);
+
+    expect(await provider.connection.getAccountInfo(abandonedMint, "confirmed")).eq(null);
+    expect(await provider.connection.getAccountInfo(abandonedIdentity, "confirmed")).eq(null);
+
+    // a new identity continues at the next index, so the closed token's PDAs are never handed out again
+    const recreateIdentityTx = await program.methods
+      .createCreatorIdentity("Dio Brando", "https://proof_url.com/")
+      .accounts({ creator: abandonedCreator.publicKey })
+      .signers([abandonedCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, recreateIdentityTx);
+    const { mint: recreatedMint, tokenIndex: recreatedIndex } = await createCreatorToken({
+      provider,
+      program,
+      signer: abandonedCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(5_000_000),
+        slope: new anchor.BN(700_000),
+        name: "Dio Coin",
+        symbol: "DIO",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+    expect(recreatedIndex).eq(1);
+    expect(recreatedMint.toBase58()).not.eq(abandonedMint.toBase58());
+  });
+
+  it("Admin suspends an identity: buys and profile updates fail, sells still work", async () => {
+    // Only the admin can suspend
+    try {
+      const tx = await program.methods
+        .suspendIdentity(1)
+        .accounts({ admin: fan.publicKey, creator: creator.publicKey })
+        .signers([fan])
+        .rpc();
+
+      assert.fail(
+        `Expected transaction to fail but it succeeded instead :${tx}`
+      );
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("Unauthorized");
+    }
+
+    const suspendReason = 7;
+    const suspendTx = await program.methods
+      .suspendIdentity(suspendReason)
+      .accounts({ admin: provider.wallet.publicKey, creator: creator.publicKey })
+      .rpc();
+    await checkConfirmTransaction(provider, suspendTx);
+
+    const identityStoredData = await program.account.identity.fetch(
+      identityAddress,
+      "confirmed"
+    );
+    expect(identityStoredData.status).deep.eq({ suspended: {} });
+    expect(identityStoredData.suspensionReason).eq(suspendReason);
+
+    try {
+      await buyCreatorToken({
+        provider,
+        program,
+        decimals: creatorToken.decimals,
+        signer: fan,
+        tokenCreator: creator.publicKey,
+        tokenToMintWholeNumber: 1,
+      });
+
+      assert.fail("Expected buy from a suspended identity to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("IdentitySuspended");
+    }
+
+    try {
+      const tx = await program.methods
+        .updateCreatorIdentity("Jonathan Joestar", "https://new_proof_url.com/")
+        .accounts({ authority: creator.publicKey, creator: creator.publicKey })
+        .signers([creator])
+        .rpc();
+
+      assert.fail(
+        `Expected transaction to fail but it succeeded instead :${tx}`
+      );
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("IdentitySuspended");
+    }
+
+    // Holders can still exit
+    const tokenToSell = new anchor.BN(1).mul(
+      new anchor.BN(10).pow(new anchor.BN(creatorToken.decimals))
+    );
+    const sellTx = await program.methods
+      .sellCreatorToken(0, tokenToSell, null)
+      .accountsPartial({
+        seller: creator.publicKey,
+        creator: creator.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, creator.publicKey),
+        ...getHolderHookAccounts(program.programId, creatorToken.address, creator.publicKey),
+      })
+      .signers([creator])
+      .rpc();
+    await checkConfirmTransaction(provider, sellTx);
+
+    const reinstateTx = await program.methods
+      .reinstateIdentity()
+      .accounts({ admin: provider.wallet.publicKey, creator: creator.publicKey })
+      .rpc();
+    await checkConfirmTransaction(provider, reinstateTx);
+
+    const { buyCreatorTokenTx } = await buyCreatorToken({
+      provider,
+      program,
+      decimals: creatorToken.decimals,
+      signer: creator,
+      tokenCreator: creator.publicKey,
+      tokenToMintWholeNumber: 1,
+    });
+    console.log("Buying works again after reinstating : ", buyCreatorTokenTx);
+  });
+
+  it("Success creating a second creator token for the same identity", async () => {
+    const identityBefore = await program.account.identity.fetch(
+      identityAddress,
+      "confirmed"
+    );
+    const tokenIndex = identityBefore.tokenCount;
+    expect(tokenIndex).eq(1);
+
+    const secondTokenAccounts = getCreatorTokenAccounts(
+      program.programId,
+      creator.publicKey,
+      tokenIndex
+    );
+    // Sending lamports to the predictable mint address doesn't block the creation
+    await airDropSOLAmount(provider, secondTokenAccounts.mint, 0.001);
+    await createCreatorToken({
+      provider,
+      program,
+      signer: creator,
+      params: {
+        decimals: 4,
+        basePrice: new anchor.BN(1_000_000),
+        slope: new anchor.BN(100_000),
+        name: "Joestar Series Two",
+        symbol: "JOJO2",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+
+    const secondCreatorToken = await program.account.creatorToken.fetch(
+      secondTokenAccounts.creatorToken,
+      "confirmed"
+    );
+    expect(secondCreatorToken.tokenIndex).eq(tokenIndex);
+    expect(secondCreatorToken.mint.toBase58()).eq(
+      secondTokenAccounts.mint.toBase58()
+    );
+
+    const identityAfter = await program.account.identity.fetch(
+      identityAddress,
+      "confirmed"
+    );
+    expect(identityAfter.tokenCount).eq(2);
+    expect(identityAfter.activeTokens).eq(2);
+
+    // Trading the second token leaves the first one untouched
+    const firstMintBefore = await getMint(
+      provider.connection,
+      creatorToken.address,
+      "confirmed",
+      TOKEN_2022_PROGRAM_ID
+    );
+    const quotedPrice = await getBuyingPriceForToken(program, 3, 4, creator.publicKey, tokenIndex);
+    const vaultBalanceBefore = await provider.connection.getBalance(
+      secondTokenAccounts.vault,
+      "confirmed"
+    );
+    await buyCreatorToken({
+      provider,
+      program,
+      decimals: 4,
+      signer: fan,
+      tokenCreator: creator.publicKey,
+      tokenToMintWholeNumber: 3,
+      tokenIndex,
+    });
+    const vaultBalanceAfter = await provider.connection.getBalance(
+      secondTokenAccounts.vault,
+      "confirmed"
+    );
+    expect(vaultBalanceAfter - vaultBalanceBefore).eq(
+      new anchor.BN(quotedPrice).toNumber()
+    );
+
+    const firstMintAfter = await getMint(
+      provider.connection,
+      creatorToken.address,
+      "confirmed",
+      TOKEN_2022_PROGRAM_ID
+    );
+    expect(firstMintAfter.supply.toString()).eq(firstMintBefore.supply.toString());
+  });
+
+  it("Creator tokens are listed in order in the registry", async () => {
+    const [configAddress] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const config = await program.account.config.fetch(configAddress, "confirmed");
+
+    const registryPage = await program.account.registryPage.fetch(
+      getRegistryPageAddress(program.programId, 0),
+      "confirmed"
+    );
+    expect(registryPage.pageIndex.toNumber()).eq(0);
+    expect(registryPage.entries.length).eq(config.creatorTokenCount.toNumber());
+
+    const [firstEntry] = registryPage.entries;
+    expect(firstEntry.mint.toBase58()).eq(creatorToken.address.toBase58());
+    expect(firstEntry.identity.toBase58()).eq(identityAddress.toBase58());
+
+    const secondToken = getCreatorTokenAccounts(program.programId, creator.publicKey, 1);
+    const lastEntry = registryPage.entries[registryPage.entries.length - 1];
+    expect(lastEntry.mint.toBase58()).eq(secondToken.mint.toBase58());
+  });
+
+  it("Creator mints are members of the platform group", async () => {
+    const [configAddress] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const config = await program.account.config.fetch(configAddress, "confirmed");
+    const groupMintAddress = getPlatformGroupAddress(program.programId);
+    expect(config.groupMint.toBase58()).eq(groupMintAddress.toBase58());
+
+    const groupMint = await getMint(provider.connection, groupMintAddress, "confirmed", TOKEN_2022_PROGRAM_ID);
+    const group = getTokenGroupState(groupMint);
+    expect(group.updateAuthority.toBase58()).eq(configAddress.toBase58());
+    expect(Number(group.size)).eq(config.creatorTokenCount.toNumber());
+
+    const firstMint = await getMint(provider.connection, creatorToken.address, "confirmed", TOKEN_2022_PROGRAM_ID);
+    const member = getTokenGroupMemberState(firstMint);
+    expect(member.group.toBase58()).eq(groupMintAddress.toBase58());
+    expect(Number(member.memberNumber)).eq(1);
+  });
+
+  it("Delegates act for the creator only within their permissions", async () => {
+    const manager = anchor.web3.Keypair.generate();
+    const PERMISSION_PROFILE = 1;
+    const PERMISSION_PAUSE_BUYS = 4;
+
+    const addTx = await program.methods
+      .addDelegate(manager.publicKey, PERMISSION_PROFILE)
+      .accounts({ creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+    await checkConfirmTransaction(provider, addTx);
+
+    // Profile edits are in scope
+    const newUrl = "https://manager_proof_url.com/";
+    const updateTx = await program.methods
+      .updateCreatorIdentity("Jonathan Joestar", newUrl)
+      .accounts({ authority: manager.publicKey, creator: creator.publicKey })
+      .signers([manager])
+      .rpc();
+    await checkConfirmTransaction(provider, updateTx);
+
+    const identityStoredData = await program.account.identity.fetch(
+      identityAddress,
+      "confirmed"
+    );
+    expect(identityStoredData.proofUrl).eq(newUrl);
+
+    // Pausing buys is not
+    try {
+      const tx = await program.methods
+        .setBuysPaused(0, true)
+        .accountsPartial({
+          authority: manager.publicKey,
+          creator: creator.publicKey,
+          creatorToken: getCreatorTokenAccounts(program.programId, creator.publicKey).creatorToken,
+        })
+        .signers([manager])
+        .rpc();
+
+      assert.fail(
+        `Expected transaction to fail but it succeeded instead :${tx}`
+      );
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("NotAuthorized");
+    }
+
+    // Widen the scope, then pause and resume buys
+    const widenTx = await program.methods
+      .addDelegate(manager.publicKey, PERMISSION_PROFILE | PERMISSION_PAUSE_BUYS)
+      .accounts({ creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+    await checkConfirmTransaction(provider, widenTx);
+
+    const pauseTx = await program.methods
+      .setBuysPaused(0, true)
+      .accountsPartial({
+        authority: manager.publicKey,
+        creator: creator.publicKey,
+        creatorToken: getCreatorTokenAccounts(program.programId, creator.publicKey).creatorToken,
+      })
+      .signers([manager])
+      .rpc();
+    await checkConfirmTransaction(provider, pauseTx);
+
+    try {
+      await buyCreatorToken({
+        provider,
+        program,
+        decimals: creatorToken.decimals,
+        signer: fan,
+        tokenCreator: creator.publicKey,
+        tokenToMintWholeNumber: 1,
+      });
+
+      assert.fail("Expected buy to fail while buys are paused");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("BuysPaused");
+    }
+
+    const resumeTx = await program.methods
+      .setBuysPaused(0, false)
+      .accountsPartial({
+        authority: creator.publicKey,
+        creator: creator.publicKey,
+        creatorToken: getCreatorTokenAccounts(program.programId, creator.publicKey).creatorToken,
+      })
+      .signers([creator])
+      .rpc();
+    await checkConfirmTransaction(provider, resumeTx);
+
+    // Revoked delegates lose access
+    const revokeTx = await program.methods
+      .revokeDelegate(manager.publicKey)
+      .accounts({ creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+    await checkConfirmTransaction(provider, revokeTx);
+
+    try {
+      const tx = await program.methods
+        .updateCreatorIdentity("Jonathan Joestar", "https://proof_url.com/")
+        .accounts({ authority: manager.publicKey, creator: creator.publicKey })
+        .signers([manager])
+        .rpc();
+
+      assert.fail(
+        `Expected transaction to fail but it succeeded instead :${tx}`
+      );
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("NotAuthorized");
+    }
+  });
+
+  it("Creator updates the token metadata, invalid values are rejected", async () => {
+    const newUri = "https://proof_url.com/updated-token-metadata.json";
+    const tx = await program.methods
+      .updateTokenMetadata(0, { uri: {} }, newUri)
+      .accountsPartial({
+        authority: creator.publicKey,
+        creator: creator.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, creator.publicKey),
+      })
+      .signers([creator])
+      .rpc();
+    await checkConfirmTransaction(provider, tx);
+
+    const tokenMetadata = await getTokenMetadata(
+      provider.connection,
+      creatorToken.address,
+      "confirmed",
+      TOKEN_2022_PROGRAM_ID
+    );
+    expect(tokenMetadata.uri).eq(newUri);
+
+    try {
+      const tx = await program.methods
+        .updateTokenMetadata(0, { symbol: {} }, "TOOLONGSYMBOL")
+        .accountsPartial({
+          authority: creator.publicKey,
+          creator: creator.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          ...getCreatorTokenAccounts(program.programId, creator.publicKey),
+        })
+        .signers([creator])
+        .rpc();
+
+      assert.fail(
+        `Expected transaction to fail but it succeeded instead :${tx}`
+      );
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("InvalidTokenSymbol");
+    }
+  });
+
+  it("Fails migrating a mint that already uses its per-mint authority", async () => {
+    try {
+      const tx = await program.methods
+        .migrateMintAuthority(0)
+        .accountsPartial({
+          payer: provider.wallet.publicKey,
+          creator: creator.publicKey,
+          creatorToken: getCreatorTokenAccounts(program.programId, creator.publicKey).creatorToken,
+          mint: creatorToken.address,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+
+      assert.fail(
+        `Expected transaction to fail but it succeeded instead :${tx}`
+      );
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("ConstraintMintMintAuthority");
+    }
+  });
+
+  it("Admin freezes a holder account: sells fail until it is thawed", async () => {
+    const creatorATA = getAssociatedTokenAddressSync(
+      creatorToken.address,
+      creator.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID,
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const creatorTokenAccounts = getCreatorTokenAccounts(program.programId, creator.publicKey);
+
+    const freezeTx = await program.methods
+      .freezeHolder(0, 1)
+      .accountsPartial({
+        admin: provider.wallet.publicKey,
+        creator: creator.publicKey,
+        creatorToken: creatorTokenAccounts.creatorToken,
+        mint: creatorTokenAccounts.mint,
+        holderAccount: creatorATA,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc();
+    await checkConfirmTransaction(provider, freezeTx);
+
+    const tokenToSell = new anchor.BN(1).mul(
+      new anchor.BN(10).pow(new anchor.BN(creatorToken.decimals))
+    );
+    try {
+      const tx = await program.methods
+        .sellCreatorToken(0, tokenToSell, null)
+        .accountsPartial({
+          seller: creator.publicKey,
+          creator: creator.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          ...creatorTokenAccounts,
+          ...getHolderHookAccounts(program.programId, creatorToken.address, creator.publicKey),
+        })
+        .signers([creator])
+        .rpc();
+
+      assert.fail(
+        `Expected transaction to fail but it succeeded instead :${tx}`
+      );
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("TokenAccountFrozen");
+    }
+
+    const thawTx = await program.methods
+      .thawHolder(0)
+      .accountsPartial({
+        admin: provider.wallet.publicKey,
+        creator: creator.publicKey,
+        creatorToken: creatorTokenAccounts.creatorToken,
+        mint: creatorTokenAccounts.mint,
+        holderAccount: creatorATA,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc();
+    await checkConfirmTransaction(provider, thawTx);
+
+    const sellTx = await program.methods
+      .sellCreatorToken(0, tokenToSell, null)
+      .accountsPartial({
+        seller: creator.publicKey,
+        creator: creator.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...creatorTokenAccounts,
+        ...getHolderHookAccounts(program.programId, creatorToken.address, creator.publicKey),
+      })
+      .signers([creator])
+      .rpc();
+    await checkConfirmTransaction(provider, sellTx);
+  });
+
+  it("Creator earns royalties on wallet-to-wallet transfers", async () => {
+    const royaltyCreator = anchor.web3.Keypair.generate();
+    const holder = anchor.web3.Keypair.generate();
+    const receiver = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, royaltyCreator.publicKey, 5);
+    await airDropSOLAmount(provider, holder.publicKey, 5);
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Joseph Joestar", "https://proof_url.com/")
+      .accounts({ creator: royaltyCreator.publicKey })
+      .signers([royaltyCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+
+    const params = {
+      decimals: 6,
+      basePrice: new anchor.BN(1_000_000),
+      slope: new anchor.BN(100_000),
+      name: "Joseph Coin",
+      symbol: "JOSEPH",
+      uri: "",
+      royaltyBps: MAX_ROYALTY_BPS * 2,
+      nonTransferable: false,
+      creatorAllocation: new anchor.BN(0),
+      vestingCliffSeconds: new anchor.BN(0),
+      vestingDurationSeconds: new anchor.BN(0),
+      earlyHolderBadges: 0,
+      tradingStartsAt: new anchor.BN(0),
+      antiSnipeSeconds: new anchor.BN(0),
+      antiSnipeMaxBuy: new anchor.BN(0),
+      antiSnipeMaxWallet: new anchor.BN(0),
+      presaleMerkleRoot: null,
+      sellTaxBps: 0,
+      sellTaxDecaySeconds: new anchor.BN(0),
+      sellTaxToCreator: false,
+    };
+
+    // Royalty above the platform cap
+    try {
+      await createCreatorToken({ provider, program, signer: royaltyCreator, params });
+      assert.fail("Expected creating a token above the royalty cap to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("RoyaltyTooHigh");
+    }
+
+    const royaltyBps = 300;
+    const { mint } = await createCreatorToken({
+      provider,
+      program,
+      signer: royaltyCreator,
+      params: { ...params, royaltyBps },
+    });
+
+    await buyCreatorToken({
+      provider,
+      program,
+      decimals: 6,
+      signer: holder,
+      tokenCreator: royaltyCreator.publicKey,
+      tokenToMintWholeNumber: 10,
+    });
+
+    const holderATA = getAssociatedTokenAddressSync(mint, holder.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+    const receiverATA = await createAssociatedTokenAccount(
+      provider.connection,
+      holder,
+      mint,
+      receiver.publicKey,
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    // the holder hook needs the receiver's record before it can receive
+    const openRecordTx = await holderHookProgram.methods
+      .openHolderRecord()
+      .accounts({ payer: holder.publicKey, mint, owner: receiver.publicKey })
+      .signers([holder])
+      .rpc();
+    await checkConfirmTransaction(provider, openRecordTx);
+
+    const transferAmount = BigInt(5_000_000);
+    const expectedFee = (transferAmount * BigInt(royaltyBps)) / BigInt(10_000);
+    await transferCheckedWithFeeAndTransferHook(
+      provider.connection,
+      holder,
+      holderATA,
+      mint,
+      receiverATA,
+      holder,
+      transferAmount,
+      6,
+      expectedFee,
+      [],
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    const withdrawTx = await program.methods
+      .harvestAndWithdrawRoyalties(0)
+      .accountsPartial({
+        authority: royaltyCreator.publicKey,
+        creator: royaltyCreator.publicKey,
+        creatorToken: getCreatorTokenAccounts(program.programId, royaltyCreator.publicKey).creatorToken,
+        mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...getHolderHookAccounts(program.programId, mint, royaltyCreator.publicKey),
+      })
+      .remainingAccounts([{ pubkey: receiverATA, isSigner: false, isWritable: true }])
+      .signers([royaltyCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, withdrawTx);
+
+    const creatorATA = getAssociatedTokenAddressSync(mint, royaltyCreator.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+    const creatorATABalance = await provider.connection.getTokenAccountBalance(creatorATA, "confirmed");
+    expect(creatorATABalance.value.amount).eq(expectedFee.toString());
+  });
+
+  it("Holder hook keeps the holder count through buys, transfers and sells", async () => {
+    const hookCreator = anchor.web3.Keypair.generate();
+    const holder = anchor.web3.Keypair.generate();
+    const receiver = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, hookCreator.publicKey, 5);
+    await airDropSOLAmount(provider, holder.publicKey, 5);
+    await airDropSOLAmount(provider, receiver.publicKey, 1);
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Jotaro Kujo", "https://proof_url.com/")
+      .accounts({ creator: hookCreator.publicKey })
+      .signers([hookCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+
+    const { mint } = await createCreatorToken({
+      provider,
+      program,
+      signer: hookCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(1_000_000),
+        slope: new anchor.BN(100_000),
+        name: "Jotaro Coin",
+        symbol: "JOTARO",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+    const { holderStats } = getHolderHookAccounts(program.programId, mint, holder.publicKey);
+    const holderCount = async () =>
+      (await holderHookProgram.account.holderStats.fetch(holderStats, "confirmed")).holderCount.toNumber();
+    expect(await holderCount()).eq(0);
+
+    await buyCreatorToken({
+      provider,
+      program,
+      decimals: 6,
+      signer: holder,
+      tokenCreator: hookCreator.publicKey,
+      tokenToMintWholeNumber: 10,
+    });
+    expect(await holderCount()).eq(1);
+    const hookCreatorToken = getCreatorTokenAccounts(program.programId, hookCreator.publicKey).creatorToken;
+    const mirroredCount = async () =>
+      (await program.account.creatorToken.fetch(hookCreatorToken, "confirmed")).holderCount.toNumber();
+    expect(await mirroredCount()).eq(1);
+
+    const openRecordTx = await holderHookProgram.methods
+      .openHolderRecord()
+      .accounts({ payer: holder.publicKey, mint, owner: receiver.publicKey })
+      .signers([holder])
+      .rpc();
+    await checkConfirmTransaction(provider, openRecordTx);
+
+    const holderATA = getAssociatedTokenAddressSync(mint, holder.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+    const receiverATA = await createAssociatedTokenAccount(
+      provider.connection,
+      holder,
+      mint,
+      receiver.publicKey,
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    // part of the balance, both wallets hold
+    await transferCheckedWithFeeAndTransferHook(
+      provider.connection,
+      holder,
+      holderATA,
+      mint,
+      receiverATA,
+      holder,
+      BigInt(4_000_000),
+      6,
+      BigInt(0),
+      [],
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+    expect(await holderCount()).eq(2);
+
+    // transfers only reach the hook, anyone syncs the count onto the creator token
+    expect(await mirroredCount()).eq(1);
+    const syncTx = await program.methods
+      .syncHolderCount(0)
+      .accountsPartial({ creator: hookCreator.publicKey, creatorToken: hookCreatorToken, holderStats })
+      .rpc();
+    await checkConfirmTransaction(provider, syncTx);
+    expect(await mirroredCount()).eq(2);
+
+    // a wallet without a holder record still receives, it just isn't counted
+    const stranger = anchor.web3.Keypair.generate();
+    const strangerATA = await createAssociatedTokenAccount(
+      provider.connection,
+      holder,
+      mint,
+      stranger.publicKey,
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+    await transferCheckedWithFeeAndTransferHook(
+      provider.connection,
+      holder,
+      holderATA,
+      mint,
+      strangerATA,
+      holder,
+      BigInt(1_000_000),
+      6,
+      BigInt(0),
+      [],
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+    expect(await holderCount()).eq(2);
+
+    // the rest, the sender stops holding
+    await transferCheckedWithFeeAndTransferHook(
+      provider.connection,
+      holder,
+      holderATA,
+      mint,
+      receiverATA,
+      holder,
+      BigInt(5_000_000),
+      6,
+      BigInt(0),
+      [],
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+    expect(await holderCount()).eq(1);
+
+    const sellTx = await program.methods
+      .sellCreatorToken(0, new anchor.BN(9_000_000), null)
+      .accountsPartial({
+        seller: receiver.publicKey,
+        creator: hookCreator.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, hookCreator.publicKey),
+        ...getHolderHookAccounts(program.programId, mint, receiver.publicKey),
+      })
+      .signers([receiver])
+      .rpc();
+    await checkConfirmTransaction(provider, sellTx);
+    expect(await holderCount()).eq(0);
+    expect(await mirroredCount()).eq(0);
+
+    const receiverRecord = await holderHookProgram.account.holderRecord.fetch(
+      getHolderHookAccounts(program.programId, mint, receiver.publicKey).holderRecord,
+      "confirmed"
+    );
+    expect(receiverRecord.balance.toNumber()).eq(0);
+    expect(receiverRecord.firstAcquiredAt.toNumber()).greaterThan(0);
+  });
+
+  it("Soulbound creator tokens trade with the curve but can't be transferred", async () => {
+    const soulboundCreator = anchor.web3.Keypair.generate();
+    const member = anchor.web3.Keypair.generate();
+    const receiver = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, soulboundCreator.publicKey, 5);
+    await airDropSOLAmount(provider, member.publicKey, 5);
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Josuke Higashikata", "https://proof_url.com/")
+      .accounts({ creator: soulboundCreator.publicKey })
+      .signers([soulboundCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+
+    const params = {
+      decimals: 6,
+      basePrice: new anchor.BN(1_000_000),
+      slope: new anchor.BN(100_000),
+      name: "Josuke Pass",
+      symbol: "JOSUKE",
+      uri: "",
+      royaltyBps: 100,
+      nonTransferable: true,
+      creatorAllocation: new anchor.BN(0),
+      vestingCliffSeconds: new anchor.BN(0),
+      vestingDurationSeconds: new anchor.BN(0),
+      earlyHolderBadges: 0,
+      tradingStartsAt: new anchor.BN(0),
+      antiSnipeSeconds: new anchor.BN(0),
+      antiSnipeMaxBuy: new anchor.BN(0),
+      antiSnipeMaxWallet: new anchor.BN(0),
+      presaleMerkleRoot: null,
+      sellTaxBps: 0,
+      sellTaxDecaySeconds: new anchor.BN(0),
+      sellTaxToCreator: false,
+    };
+
+    // Royalties are only charged on transfers
+    try {
+      await createCreatorToken({ provider, program, signer: soulboundCreator, params });
+      assert.fail("Expected creating a soulbound token with royalties to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("RoyaltyOnNonTransferable");
+    }
+
+    const { mint, creatorToken: creatorTokenAddress } = await createCreatorToken({
+      provider,
+      program,
+      signer: soulboundCreator,
+      params: { ...params, royaltyBps: 0 },
+    });
+    const creatorTokenAccount = await program.account.creatorToken.fetch(creatorTokenAddress, "confirmed");
+    expect(creatorTokenAccount.nonTransferable).eq(true);
+
+    await buyCreatorToken({
+      provider,
+      program,
+      decimals: 6,
+      signer: member,
+      tokenCreator: soulboundCreator.publicKey,
+      tokenToMintWholeNumber: 10,
+    });
+
+    const memberATA = getAssociatedTokenAddressSync(mint, member.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+    const receiverATA = await createAssociatedTokenAccount(
+      provider.connection,
+      member,
+      mint,
+      receiver.publicKey,
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+    const openRecordTx = await holderHookProgram.methods
+      .openHolderRecord()
+      .accounts({ payer: member.publicKey, mint, owner: receiver.publicKey })
+      .signers([member])
+      .rpc();
+    await checkConfirmTransaction(provider, openRecordTx);
+
+    try {
+      const tx = await transferCheckedWithTransferHook(
+        provider.connection,
+        member,
+        memberATA,
+        mint,
+        receiverATA,
+        member,
+        BigInt(1_000_000),
+        6,
+        [],
+        { commitment: "confirmed" },
+        TOKEN_2022_PROGRAM_ID
+      );
+      assert.fail(`Expected the transfer to fail but it succeeded instead :${tx}`);
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      // Token-2022 NonTransferable error
+      expect(err.logs.join("\n")).to.include("Transfer is disabled for this mint");
+    }
+
+    // Selling back to the curve burns as usual
+    const sellTx = await program.methods
+      .sellCreatorToken(0, new anchor.BN(10_000_000), null)
+      .accountsPartial({
+        seller: member.publicKey,
+        creator: soulboundCreator.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, soulboundCreator.publicKey),
+        ...getHolderHookAccounts(program.programId, mint, member.publicKey),
+      })
+      .signers([member])
+      .rpc();
+    await checkConfirmTransaction(provider, sellTx);
+
+    const memberBalance = await provider.connection.getTokenAccountBalance(memberATA, "confirmed");
+    expect(memberBalance.value.amount).eq("0");
+  });
+
+  it("Creator allocation vests into escrow without moving the curve price", async () => {
+    const vestingCreator = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, vestingCreator.publicKey, 5);
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Giorno Giovanna", "https://proof_url.com/")
+      .accounts({ creator: vestingCreator.publicKey })
+      .signers([vestingCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+
+    const creatorAllocation = new anchor.BN(1_000_000_000);
+    const params = {
+      decimals: 6,
+      basePrice: new anchor.BN(1_000_000),
+      slope: new anchor.BN(100_000),
+      name: "Giorno Coin",
+      symbol: "GIORNO",
+      uri: "",
+      royaltyBps: 0,
+      nonTransferable: false,
+      creatorAllocation,
+      vestingCliffSeconds: new anchor.BN(3600),
+      vestingDurationSeconds: new anchor.BN(7200),
+      earlyHolderBadges: 0,
+      tradingStartsAt: new anchor.BN(0),
+      antiSnipeSeconds: new anchor.BN(0),
+      antiSnipeMaxBuy: new anchor.BN(0),
+      antiSnipeMaxWallet: new anchor.BN(0),
+      presaleMerkleRoot: null,
+      sellTaxBps: 0,
+      sellTaxDecaySeconds: new anchor.BN(0),
+      sellTaxToCreator: false,
+    };
+
+    // Cliff past the end of the schedule
+    try {
+      await createCreatorToken({
+        provider,
+        program,
+        signer: vestingCreator,
+        params: { ...params, vestingCliffSeconds: new anchor.BN(7201) },
+      });
+      assert.fail("Expected creating a token with an invalid vesting schedule to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("InvalidVestingSchedule");
+    }
+
+    const cliffToken = await createCreatorToken({ provider, program, signer: vestingCreator, params });
+    const cliffVesting = getVestingAccounts(program.programId, cliffToken.creatorToken, cliffToken.mint);
+    const escrowBalance = await provider.connection.getTokenAccountBalance(cliffVesting.vestingEscrow, "confirmed");
+    expect(escrowBalance.value.amount).eq(creatorAllocation.toString());
+
+    // The allocation isn't part of the curve supply, quotes match an empty curve
+    const quote = await getBuyingPriceForToken(program, 1, 6, vestingCreator.publicKey, cliffToken.tokenIndex);
+    expect(quote.toNumber()).eq(params.basePrice.toNumber() + params.slope.toNumber() / 2);
+
+    const claimAccounts = (token: typeof cliffToken) => ({
+      creator: vestingCreator.publicKey,
+      creatorToken: token.creatorToken,
+      mint: token.mint,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+      ...getVestingAccounts(program.programId, token.creatorToken, token.mint),
+      ...getHolderHookAccounts(program.programId, token.mint, vestingCreator.publicKey),
+    });
+
+    // Nothing is claimable before the cliff
+    try {
+      const tx = await program.methods
+        .claimVested(cliffToken.tokenIndex)
+        .accountsPartial(claimAccounts(cliffToken))
+        .signers([vestingCreator])
+        .rpc();
+      assert.fail(`Expected claiming before the cliff to fail but it succeeded instead :${tx}`);
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("NothingToClaim");
+    }
+
+    // Short schedule that is fully vested after a few seconds
+    const shortToken = await createCreatorToken({
+      provider,
+      program,
+      signer: vestingCreator,
+      params: {
+        ...params,
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(2),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    const claimTx = await program.methods
+      .claimVested(shortToken.tokenIndex)
+      .accountsPartial(claimAccounts(shortToken))
+      .signers([vestingCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, claimTx);
+
+    const creatorATA = getAssociatedTokenAddressSync(shortToken.mint, vestingCreator.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+    const creatorBalance = await provider.connection.getTokenAccountBalance(creatorATA, "confirmed");
+    expect(creatorBalance.value.amount).eq(creatorAllocation.toString());
+
+    const vesting = await program.account.creatorVesting.fetch(
+      getVestingAccounts(program.programId, shortToken.creatorToken, shortToken.mint).vesting,
+      "confirmed"
+    );
+    expect(vesting.claimedAmount.toString()).eq(creatorAllocation.toString());
+
+    // Sells are capped at what buyers own, the claimed allocation can't take a fan's payment out of the vault
+    const vestingFan = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, vestingFan.publicKey, 5);
+    await buyCreatorToken({
+      provider,
+      program,
+      decimals: 6,
+      signer: vestingFan,
+      tokenCreator: vestingCreator.publicKey,
+      tokenToMintWholeNumber: 1,
+      tokenIndex: shortToken.tokenIndex,
+    });
+    try {
+      const tx = await program.methods
+        .sellCreatorToken(shortToken.tokenIndex, new anchor.BN(2_000_000), null)
+        .accountsPartial({
+          seller: vestingCreator.publicKey,
+          creator: vestingCreator.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          ...getCreatorTokenAccounts(program.programId, vestingCreator.publicKey, shortToken.tokenIndex),
+          ...getHolderHookAccounts(program.programId, shortToken.mint, vestingCreator.publicKey),
+        })
+        .signers([vestingCreator])
+        .rpc();
+      assert.fail(`Expected selling past the curve supply to fail but it succeeded instead :${tx}`);
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("InsufficientCurveSupply");
+    }
+  });
+
+  it("Creator picks legacy SPL Token, extension features are rejected", async () => {
+    const legacyCreator = anchor.web3.Keypair.generate();
+    const legacyFan = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, legacyCreator.publicKey, 5);
+    await airDropSOLAmount(provider, legacyFan.publicKey, 5);
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Jolyne Cujoh", "https://proof_url.com/")
+      .accounts({ creator: legacyCreator.publicKey })
+      .signers([legacyCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+
+    const params = {
+      decimals: 6,
+      basePrice: new anchor.BN(1_000_000),
+      slope: new anchor.BN(100_000),
+      name: "Jolyne Coin",
+      symbol: "JOLYNE",
+      uri: "",
+      royaltyBps: 100,
+      nonTransferable: false,
+      creatorAllocation: new anchor.BN(0),
+      vestingCliffSeconds: new anchor.BN(0),
+      vestingDurationSeconds: new anchor.BN(0),
+      earlyHolderBadges: 0,
+      tradingStartsAt: new anchor.BN(0),
+      antiSnipeSeconds: new anchor.BN(0),
+      antiSnipeMaxBuy: new anchor.BN(0),
+      antiSnipeMaxWallet: new anchor.BN(0),
+      presaleMerkleRoot: null,
+      sellTaxBps: 0,
+      sellTaxDecaySeconds: new anchor.BN(0),
+      sellTaxToCreator: false,
+    };
+
+    // Transfer fees are a Token-2022 extension
+    try {
+      await createCreatorToken({ provider, program, signer: legacyCreator, params, tokenProgram: TOKEN_PROGRAM_ID });
+      assert.fail("Expected creating a legacy token with royalties to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("Token2022Required");
+    }
+
+    const { mint, creatorToken: creatorTokenAddress } = await createCreatorToken({
+      provider,
+      program,
+      signer: legacyCreator,
+      params: { ...params, royaltyBps: 0 },
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+    const creatorTokenAccount = await program.account.creatorToken.fetch(creatorTokenAddress, "confirmed");
+    expect(creatorTokenAccount.tokenProgram.toBase58()).eq(TOKEN_PROGRAM_ID.toBase58());
+    expect(creatorTokenAccount.hasHolderHook).eq(false);
+
+    const legacyMint = await getMint(provider.connection, mint, "confirmed", TOKEN_PROGRAM_ID);
+    expect(legacyMint.supply).eq(BigInt(0));
+
+    const { tokenBought } = await buyCreatorToken({
+      provider,
+      program,
+      decimals: 6,
+      signer: legacyFan,
+      tokenCreator: legacyCreator.publicKey,
+      tokenToMintWholeNumber: 5,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+
+    const sellTx = await program.methods
+      .sellCreatorToken(0, tokenBought, null)
+      .accountsPartial({
+        seller: legacyFan.publicKey,
+        creator: legacyCreator.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, legacyCreator.publicKey),
+        ...getHolderHookAccounts(program.programId, mint, legacyFan.publicKey),
+      })
+      .signers([legacyFan])
+      .rpc();
+    await checkConfirmTransaction(provider, sellTx);
+
+    const fanATA = getAssociatedTokenAddressSync(mint, legacyFan.publicKey, undefined, TOKEN_PROGRAM_ID);
+    const fanBalance = await provider.connection.getTokenAccountBalance(fanATA, "confirmed");
+    expect(fanBalance.value.amount).eq("0");
+
+    // The legacy mint can't be closed and outlives the token
+    const closeTokenTx = await program.methods
+      .closeCreatorToken(0)
+      .accountsPartial({
+        creator: legacyCreator.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        ...getCreatorTokenAccounts(program.programId, legacyCreator.publicKey),
+        vesting: null,
+        vestingEscrow: null,
+        holderHookProgram: null,
+        hookAuthority: null,
+        extraAccountMetaList: null,
+        holderStats: null,
+      })
+      .signers([legacyCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, closeTokenTx);
+    const closeIdentityTx = await program.methods
+      .closeCreatorIdentity()
+      .accounts({ creator: legacyCreator.publicKey })
+      .signers([legacyCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, closeIdentityTx);
+    expect(await provider.connection.getAccountInfo(mint, "confirmed")).not.eq(null);
+
+    const recreateIdentityTx = await program.methods
+      .createCreatorIdentity("Jolyne Cujoh", "https://proof_url.com/")
+      .accounts({ creator: legacyCreator.publicKey })
+      .signers([legacyCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, recreateIdentityTx);
+
+    // The new identity picks up at the next index, the leftover mint and its PDAs stay with the old token
+    const { mint: recreatedMint, tokenIndex: recreatedIndex } = await createCreatorToken({
+      provider,
+      program,
+      signer: legacyCreator,
+      params: { ...params, royaltyBps: 0 },
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+    expect(recreatedIndex).eq(1);
+    expect(recreatedMint.toBase58()).not.eq(mint.toBase58());
+
+    await buyCreatorToken({
+      provider,
+      program,
+      decimals: 6,
+      signer: legacyFan,
+      tokenCreator: legacyCreator.publicKey,
+      tokenToMintWholeNumber: 1,
+      tokenIndex: recreatedIndex,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+    const rebuyATA = getAssociatedTokenAddressSync(recreatedMint, legacyFan.publicKey, undefined, TOKEN_PROGRAM_ID);
+    const rebuyBalance = await provider.connection.getTokenAccountBalance(rebuyATA, "confirmed");
+    expect(rebuyBalance.value.amount).eq("1000000");
+  });
+  it("First buyers get a soulbound early holder badge", async () => {
+    const badgeCreator = anchor.web3.Keypair.generate();
+    const fans = [anchor.web3.Keypair.generate(), anchor.web3.Keypair.generate(), anchor.web3.Keypair.generate()];
+    for (const wallet of [badgeCreator, ...fans]) {
+      await airDropSOLAmount(provider, wallet.publicKey, 5);
+    }
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Giorno Giovanna", "https://proof_url.com/")
+      .accounts({ creator: badgeCreator.publicKey })
+      .signers([badgeCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+
+    const { mint, creatorToken: creatorTokenAddress } = await createCreatorToken({
+      provider,
+      program,
+      signer: badgeCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(1_000_000),
+        slope: new anchor.BN(100_000),
+        name: "Giorno Coin",
+        symbol: "GIORNO",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 2,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+
+    // lamports sent to a badge address ahead of time don't make the buy that mints it revert
+    const { badgeMint: prefundedBadge } = getBadgeAccounts(program.programId, creatorTokenAddress, fans[1].publicKey);
+    await airDropSOLAmount(provider, prefundedBadge, 0.001);
+
+    // the first fan buys twice, the repeat buy doesn't take a second badge
+    for (const buyer of [fans[0], fans[0], fans[1], fans[2]]) {
+      await buyCreatorToken({
+        provider,
+        program,
+        decimals: 6,
+        signer: buyer,
+        tokenCreator: badgeCreator.publicKey,
+        tokenToMintWholeNumber: 1,
+      });
+    }
+
+    const creatorTokenAccount = await program.account.creatorToken.fetch(creatorTokenAddress, "confirmed");
+    expect(creatorTokenAccount.earlyHolderLimit).eq(2);
+    expect(creatorTokenAccount.earlyHolderCount).eq(2);
+
+    for (const [i, buyer] of fans.slice(0, 2).entries()) {
+      const { badgeMint, badgeAta } = getBadgeAccounts(program.programId, creatorTokenAddress, buyer.publicKey);
+      const badge = await getMint(provider.connection, badgeMint, "confirmed", TOKEN_2022_PROGRAM_ID);
+      expect(badge.supply).eq(BigInt(1));
+      expect(badge.decimals).eq(0);
+      expect(badge.mintAuthority).eq(null);
+
+      const badgeBalance = await provider.connection.getTokenAccountBalance(badgeAta, "confirmed");
+      expect(badgeBalance.value.amount).eq("1");
+
+      const metadata = await getTokenMetadata(provider.connection, badgeMint, "confirmed");
+      expect(metadata.name).eq(`Early Holder #${i + 1}`);
+      const fields = Object.fromEntries(metadata.additionalMetadata);
+      expect(fields["rank"]).eq(`${i + 1}`);
+      expect(fields["creator"]).eq(badgeCreator.publicKey.toBase58());
+      expect(fields["creator_mint"]).eq(mint.toBase58());
+    }
+
+    const { badgeMint: lateBadgeMint } = getBadgeAccounts(program.programId, creatorTokenAddress, fans[2].publicKey);
+    expect(await provider.connection.getAccountInfo(lateBadgeMint, "confirmed")).eq(null);
+
+    // badges are soulbound
+    const { badgeMint, badgeAta } = getBadgeAccounts(program.programId, creatorTokenAddress, fans[0].publicKey);
+    const recipientAta = await createAssociatedTokenAccount(
+      provider.connection,
+      fans[1],
+      badgeMint,
+      fans[1].publicKey,
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+    try {
+      await transferChecked(
+        provider.connection,
+        fans[0],
+        badgeAta,
+        badgeMint,
+        recipientAta,
+        fans[0],
+        1,
+        0,
+        [],
+        { commitment: "confirmed" },
+        TOKEN_2022_PROGRAM_ID
+      );
+      assert.fail("Expected transferring a badge to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+    }
+  });
+  it("Referrers earn a share of the trading fee and claim it", async () => {
+    const referralCreator = anchor.web3.Keypair.generate();
+    const affiliate = anchor.web3.Keypair.generate();
+    const referredFan = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, referralCreator.publicKey, 5);
+    await airDropSOLAmount(provider, affiliate.publicKey, 5);
+    await airDropSOLAmount(provider, referredFan.publicKey, 5);
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Jotaro Kujo", "https://proof_url.com/")
+      .accounts({ creator: referralCreator.publicKey })
+      .signers([referralCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+    const { mint } = await createCreatorToken({
+      provider,
+      program,
+      signer: referralCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(1_000_000),
+        slope: new anchor.BN(100_000),
+        name: "Jotaro Coin",
+        symbol: "JOTARO",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+
+    // 1% trading fee, a quarter of it to the referrer
+    const feesTx = await program.methods.setTradeFees(100, 2_500).accounts({ admin: provider.wallet.publicKey }).rpc();
+    await checkConfirmTransaction(provider, feesTx);
+
+    const registerTx = await program.methods
+      .registerReferrer()
+      .accounts({ authority: affiliate.publicKey })
+      .signers([affiliate])
+      .rpc();
+    await checkConfirmTransaction(provider, registerTx);
+    const referrer = getReferrerAddress(program.programId, affiliate.publicKey);
+
+    const tradeLamports = await getBuyingPriceForToken(program, 2, 6, referralCreator.publicKey);
+    const { tokenBought } = await buyCreatorToken({
+      provider,
+      program,
+      decimals: 6,
+      signer: referredFan,
+      tokenCreator: referralCreator.publicKey,
+      tokenToMintWholeNumber: 2,
+      referrer,
+    });
+    const buyReferral = tradeLamports.muln(100).divn(10_000).muln(2_500).divn(10_000);
+    let referrerAccount = await program.account.referrer.fetch(referrer, "confirmed");
+    expect(referrerAccount.totalEarned.toString()).eq(buyReferral.toString());
+
+    // affiliates can't refer themselves
+    try {
+      await buyCreatorToken({
+        provider,
+        program,
+        decimals: 6,
+        signer: affiliate,
+        tokenCreator: referralCreator.publicKey,
+        tokenToMintWholeNumber: 1,
+        referrer,
+      });
+      assert.fail("Expected a self-referred buy to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("SelfReferral");
+    }
+
+    const sellReturn = await getSellingPriceForToken(program, 2, 6, referralCreator.publicKey);
+    const sellTx = await program.methods
+      .sellCreatorToken(0, tokenBought, null)
+      .accountsPartial({
+        seller: referredFan.publicKey,
+        creator: referralCreator.publicKey,
+        referrer,
+        ...getCreatorTokenAccounts(program.programId, referralCreator.publicKey),
+        ...getHolderHookAccounts(program.programId, mint, referredFan.publicKey),
+      })
+      .signers([referredFan])
+      .rpc();
+    await checkConfirmTransaction(provider, sellTx);
+    const sellReferral = sellReturn.muln(100).divn(10_000).muln(2_500).divn(10_000);
+    referrerAccount = await program.account.referrer.fetch(referrer, "confirmed");
+    expect(referrerAccount.totalEarned.toString()).eq(buyReferral.add(sellReferral).toString());
+
+    const affiliateBalance = await provider.connection.getBalance(affiliate.publicKey, "confirmed");
+    const claimTx = await program.methods
+      .claimReferralFees()
+      .accounts({ authority: affiliate.publicKey })
+      .signers([affiliate])
+      .rpc();
+    await checkConfirmTransaction(provider, claimTx);
+    const claimFee = 5_000;
+    expect(await provider.connection.getBalance(affiliate.publicKey, "confirmed")).eq(
+      affiliateBalance + buyReferral.add(sellReferral).toNumber() - claimFee
+    );
+    referrerAccount = await program.account.referrer.fetch(referrer, "confirmed");
+    expect(referrerAccount.totalClaimed.toString()).eq(referrerAccount.totalEarned.toString());
+
+    // the platform share goes to the admin
+    const withdrawTx = await program.methods.withdrawPlatformFees().accounts({ admin: provider.wallet.publicKey }).rpc();
+    await checkConfirmTransaction(provider, withdrawTx);
+
+    const resetTx = await program.methods.setTradeFees(0, 0).accounts({ admin: provider.wallet.publicKey }).rpc();
+    await checkConfirmTransaction(provider, resetTx);
+  });
+  it("Trades signed against an expired quote are rejected", async () => {
+    const lateFan = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, lateFan.publicKey, 5);
+    const now = await provider.connection.getBlockTime(await provider.connection.getSlot("confirmed"));
+
+    try {
+      await buyCreatorToken({
+        provider,
+        program,
+        decimals: 4,
+        signer: lateFan,
+        tokenCreator: creator.publicKey,
+        tokenToMintWholeNumber: 1,
+        tokenIndex: 1,
+        expiresAt: new anchor.BN(now - 60),
+      });
+      assert.fail("Expected a buy past its deadline to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("QuoteExpired");
+    }
+
+    const { tokenBought } = await buyCreatorToken({
+      provider,
+      program,
+      decimals: 4,
+      signer: lateFan,
+      tokenCreator: creator.publicKey,
+      tokenToMintWholeNumber: 1,
+      tokenIndex: 1,
+      expiresAt: new anchor.BN(now + 60),
+    });
+
+    const creatorTokenAccounts = getCreatorTokenAccounts(program.programId, creator.publicKey, 1);
+    try {
+      await program.methods
+        .sellCreatorToken(1, tokenBought, new anchor.BN(now - 60))
+        .accountsPartial({
+          seller: lateFan.publicKey,
+          creator: creator.publicKey,
+          ...creatorTokenAccounts,
+          ...getHolderHookAccounts(program.programId, creatorTokenAccounts.mint, lateFan.publicKey),
+        })
+        .signers([lateFan])
+        .rpc();
+      assert.fail("Expected a sell past its deadline to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      const anchorErrCode = err.error.errorCode.code;
+      expect(anchorErrCode).eq("QuoteExpired");
+    }
+  });
+  it("Fan buys several creator tokens in one instruction", async () => {
+    const bundleCreators = [anchor.web3.Keypair.generate(), anchor.web3.Keypair.generate()];
+    const bundleFan = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, bundleFan.publicKey, 5);
+
+    const mints: anchor.web3.PublicKey[] = [];
+    for (const [i, bundleCreator] of bundleCreators.entries()) {
+      await airDropSOLAmount(provider, bundleCreator.publicKey, 5);
+      const identityTx = await program.methods
+        .createCreatorIdentity(`Bundle Creator ${i}`, "https://proof_url.com/")
+        .accounts({ creator: bundleCreator.publicKey })
+        .signers([bundleCreator])
+        .rpc();
+      await checkConfirmTransaction(provider, identityTx);
+      const { mint } = await createCreatorToken({
+        provider,
+        program,
+        signer: bundleCreator,
+        params: {
+          decimals: 6,
+          basePrice: new anchor.BN(1_000_000),
+          slope: new anchor.BN(100_000),
+          name: `Bundle Coin ${i}`,
+          symbol: `BUNDLE${i}`,
+          uri: "",
+          royaltyBps: 0,
+          nonTransferable: false,
+          creatorAllocation: new anchor.BN(0),
+          vestingCliffSeconds: new anchor.BN(0),
+          vestingDurationSeconds: new anchor.BN(0),
+          earlyHolderBadges: 0,
+          tradingStartsAt: new anchor.BN(0),
+          antiSnipeSeconds: new anchor.BN(0),
+          antiSnipeMaxBuy: new anchor.BN(0),
+          antiSnipeMaxWallet: new anchor.BN(0),
+          presaleMerkleRoot: null,
+          sellTaxBps: 0,
+          sellTaxDecaySeconds: new anchor.BN(0),
+          sellTaxToCreator: false,
+        },
+      });
+      mints.push(mint);
+    }
+
+    const tokensToBuy = new anchor.BN(3_000_000);
+    const costs = await Promise.all(bundleCreators.map((c) => getBuyingPriceForToken(program, 3, 6, c.publicKey)));
+    const { holderHookProgram: hookProgram, hookAuthority } = getHolderHookAccounts(program.programId, mints[0], bundleFan.publicKey);
+    const remainingAccounts = bundleCreators.flatMap((c) => getBuyLegAccounts(program.programId, c.publicKey, bundleFan.publicKey));
+
+    // the second leg's max cost is too low, nothing is bought
+    try {
+      await program.methods
+        .buyMany(
+          [
+            { creator: bundleCreators[0].publicKey, tokenIndex: 0, tokensToBuy, maxCost: costs[0] },
+            { creator: bundleCreators[1].publicKey, tokenIndex: 0, tokensToBuy, maxCost: costs[1].subn(1) },
+          ],
+          null
+        )
+        .accountsPartial({ buyer: bundleFan.publicKey, holderHookProgram: hookProgram, hookAuthority })
+        .remainingAccounts(remainingAccounts)
+        .signers([bundleFan])
+        .rpc();
+      assert.fail("Expected the bundle to fail on its second leg");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      // the leg's own error comes back, the logs name the leg
+      expect(err.error.errorCode.code).eq("SlippageExceeded");
+      expect(err.logs).to.include("Program log: Buy leg 1 failed");
+    }
+    expect(await provider.connection.getAccountInfo(remainingAccounts[5].pubkey, "confirmed")).eq(null);
+
+    const bundleTx = await program.methods
+      .buyMany(
+        bundleCreators.map((c, i) => ({ creator: c.publicKey, tokenIndex: 0, tokensToBuy, maxCost: costs[i] })),
+        null
+      )
+      .accountsPartial({ buyer: bundleFan.publicKey, holderHookProgram: hookProgram, hookAuthority })
+      .remainingAccounts(remainingAccounts)
+      .signers([bundleFan])
+      .rpc();
+    await checkConfirmTransaction(provider, bundleTx);
+
+    for (const mint of mints) {
+      const fanAta = getAssociatedTokenAddressSync(mint, bundleFan.publicKey, false, TOKEN_2022_PROGRAM_ID);
+      const balance = await provider.connection.getTokenAccountBalance(fanAta, "confirmed");
+      expect(balance.value.amount).eq(tokensToBuy.toString());
+    }
+  });
+  it("Fan swaps one creator token for another without the SOL leaving the vaults", async () => {
+    const [creatorIn, creatorOut, swapper] = [anchor.web3.Keypair.generate(), anchor.web3.Keypair.generate(), anchor.web3.Keypair.generate()];
+    await airDropSOLAmount(provider, swapper.publicKey, 5);
+
+    const mints: anchor.web3.PublicKey[] = [];
+    for (const [i, swapCreator] of [creatorIn, creatorOut].entries()) {
+      await airDropSOLAmount(provider, swapCreator.publicKey, 5);
+      const identityTx = await program.methods
+        .createCreatorIdentity(`Swap Creator ${i}`, "https://proof_url.com/")
+        .accounts({ creator: swapCreator.publicKey })
+        .signers([swapCreator])
+        .rpc();
+      await checkConfirmTransaction(provider, identityTx);
+      const { mint } = await createCreatorToken({
+        provider,
+        program,
+        signer: swapCreator,
+        params: {
+          decimals: 6,
+          basePrice: new anchor.BN(1_000_000),
+          slope: new anchor.BN(100_000 * (i + 1)),
+          name: `Swap Coin ${i}`,
+          symbol: `SWAP${i}`,
+          uri: "",
+          royaltyBps: 0,
+          nonTransferable: false,
+          creatorAllocation: new anchor.BN(0),
+          vestingCliffSeconds: new anchor.BN(0),
+          vestingDurationSeconds: new anchor.BN(0),
+          earlyHolderBadges: 0,
+          tradingStartsAt: new anchor.BN(0),
+          antiSnipeSeconds: new anchor.BN(0),
+          antiSnipeMaxBuy: new anchor.BN(0),
+          antiSnipeMaxWallet: new anchor.BN(0),
+          presaleMerkleRoot: null,
+          sellTaxBps: 0,
+          sellTaxDecaySeconds: new anchor.BN(0),
+          sellTaxToCreator: false,
+        },
+      });
+      mints.push(mint);
+    }
+
+    const { tokenBought } = await buyCreatorToken({
+      provider,
+      program,
+      decimals: 6,
+      signer: swapper,
+      tokenCreator: creatorIn.publicKey,
+      tokenToMintWholeNumber: 5,
+    });
+
+    const accountsIn = getCreatorTokenAccounts(program.programId, creatorIn.publicKey);
+    const accountsOut = getCreatorTokenAccounts(program.programId, creatorOut.publicKey);
+    const quoteAccounts = {
+      creatorIn: creatorIn.publicKey,
+      creatorTokenIn: accountsIn.creatorToken,
+      mintIn: accountsIn.mint,
+      creatorOut: creatorOut.publicKey,
+      creatorTokenOut: accountsOut.creatorToken,
+      mintOut: accountsOut.mint,
+    };
+    const quote = await program.methods.getSwapQuote(0, 0, tokenBought).accountsPartial(quoteAccounts).view();
+    const quotedOut: anchor.BN = quote.amountOut;
+    expect(quotedOut.gtn(0)).eq(true);
+
+    const hookIn = getHolderHookAccounts(program.programId, mints[0], swapper.publicKey);
+    const hookOut = getHolderHookAccounts(program.programId, mints[1], swapper.publicKey);
+    const swapAccounts = {
+      user: swapper.publicKey,
+      ...quoteAccounts,
+      vaultIn: accountsIn.vault,
+      vaultOut: accountsOut.vault,
+      tokenProgramIn: TOKEN_2022_PROGRAM_ID,
+      tokenProgramOut: TOKEN_2022_PROGRAM_ID,
+      holderHookProgram: hookIn.holderHookProgram,
+      hookAuthority: hookIn.hookAuthority,
+      holderRecordIn: hookIn.holderRecord,
+      holderStatsIn: hookIn.holderStats,
+      holderRecordOut: hookOut.holderRecord,
+      holderStatsOut: hookOut.holderStats,
+    };
+
+    // asking for more than the quote fails
+    try {
+      await program.methods
+        .swapCreatorTokens(0, 0, tokenBought, quotedOut.addn(1), null)
+        .accountsPartial(swapAccounts)
+        .signers([swapper])
+        .rpc();
+      assert.fail("Expected a swap below its minimum output to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      expect(err.error.errorCode.code).eq("SlippageExceeded");
+    }
+
+    const [feeVault] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("fee_vault")], program.programId);
+    const feeVaultBalance = await provider.connection.getBalance(feeVault, "confirmed");
+    const vaultInBalance = await provider.connection.getBalance(accountsIn.vault, "confirmed");
+    const vaultOutBalance = await provider.connection.getBalance(accountsOut.vault, "confirmed");
+    const costOut = await program.methods
+      .getBuyingTokenPrice(0, quotedOut)
+      .accountsPartial({ creator: creatorOut.publicKey, ...accountsOut })
+      .view();
+    const sellReturn = await program.methods
+      .getSellingReturnPrice(0, tokenBought)
+      .accountsPartial({ creator: creatorIn.publicKey, ...accountsIn })
+      .view();
+
+    const swapTx = await program.methods
+      .swapCreatorTokens(0, 0, tokenBought, quotedOut, null)
+      .accountsPartial(swapAccounts)
+      .signers([swapper])
+      .rpc();
+    await checkConfirmTransaction(provider, swapTx);
+
+    const ataIn = getAssociatedTokenAddressSync(mints[0], swapper.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const ataOut = getAssociatedTokenAddressSync(mints[1], swapper.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    expect((await provider.connection.getTokenAccountBalance(ataIn, "confirmed")).value.amount).eq("0");
+    expect((await provider.connection.getTokenAccountBalance(ataOut, "confirmed")).value.amount).eq(quotedOut.toString());
+    expect(await provider.connection.getBalance(accountsOut.vault, "confirmed")).eq(vaultOutBalance + costOut.toNumber());
+
+    // the proceeds leave the `_in` vault in full, what the buy doesn't use is refunded to the swapper
+    const fee = (await provider.connection.getBalance(feeVault, "confirmed")) - feeVaultBalance;
+    expect(await provider.connection.getBalance(accountsIn.vault, "confirmed")).eq(vaultInBalance - sellReturn.toNumber());
+    expect(quote.refund.toNumber()).eq(sellReturn.toNumber() - fee - costOut.toNumber());
+  });
+  it("Payer gifts creator tokens to a recipient", async () => {
+    const gifter = anchor.web3.Keypair.generate();
+    const recipient = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, gifter.publicKey, 5);
+
+    const tokenAccounts = getCreatorTokenAccounts(program.programId, creator.publicKey, 1);
+    const tokensToGift = new anchor.BN(2_0000);
+    const giftTx = await program.methods
+      .giftCreatorToken(1, tokensToGift, null, null)
+      .accountsPartial({
+        payer: gifter.publicKey,
+        recipient: recipient.publicKey,
+        creator: creator.publicKey,
+        ...tokenAccounts,
+        ...getHolderHookAccounts(program.programId, tokenAccounts.mint, recipient.publicKey),
+        ...getBadgeAccounts(program.programId, tokenAccounts.creatorToken, recipient.publicKey),
+        // the token has no launch window to track
+        launchBuys: null,
+      })
+      .signers([gifter])
+      .rpc();
+    await checkConfirmTransaction(provider, giftTx);
+
+    const recipientAta = getAssociatedTokenAddressSync(tokenAccounts.mint, recipient.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const recipientBalance = await provider.connection.getTokenAccountBalance(recipientAta, "confirmed");
+    expect(recipientBalance.value.amount).eq(tokensToGift.toString());
+    expect(await provider.connection.getAccountInfo(recipient.publicKey, "confirmed")).eq(null);
+
+    const tx = await provider.connection.getTransaction(giftTx, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const events = [...new anchor.EventParser(program.programId, program.coder).parseLogs(tx.meta.logMessages)];
+    const gifted = events.find((event) => event.name === "creatorTokenGifted");
+    expect(gifted.data.payer.toBase58()).eq(gifter.publicKey.toBase58());
+    expect(gifted.data.recipient.toBase58()).eq(recipient.publicKey.toBase58());
+    expect(gifted.data.tokens.toString()).eq(tokensToGift.toString());
+  });
+  it("Keeper fills a sell limit order once the curve reaches its price", async () => {
+    const orderCreator = anchor.web3.Keypair.generate();
+    const seller = anchor.web3.Keypair.generate();
+    const keeper = anchor.web3.Keypair.generate();
+    for (const wallet of [orderCreator, seller, keeper]) {
+      await airDropSOLAmount(provider, wallet.publicKey, 5);
+    }
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Josuke Higashikata", "https://proof_url.com/")
+      .accounts({ creator: orderCreator.publicKey })
+      .signers([orderCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+    const { mint, creatorToken: creatorTokenAddress } = await createCreatorToken({
+      provider,
+      program,
+      signer: orderCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(1_000_000),
+        slope: new anchor.BN(100_000),
+        name: "Josuke Coin",
+        symbol: "JOSUKE",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+    await buyCreatorToken({ provider, program, decimals: 6, signer: seller, tokenCreator: orderCreator.publicKey, tokenToMintWholeNumber: 10 });
+
+    // marginal price is 2_000_000 lamports at 10 tokens
+    const tokenAccounts = getCreatorTokenAccounts(program.programId, orderCreator.publicKey);
+    const hookAccounts = getHolderHookAccounts(program.programId, mint, seller.publicKey);
+    const placeTx = await program.methods
+      .placeLimitOrder(0, new anchor.BN(1), { sell: {} }, new anchor.BN(5_000_000), new anchor.BN(2_500_000), new anchor.BN(0))
+      .accountsPartial({ owner: seller.publicKey, creator: orderCreator.publicKey, ...tokenAccounts, ...hookAccounts })
+      .signers([seller])
+      .rpc();
+    await checkConfirmTransaction(provider, placeTx);
+
+    const order = getLimitOrderAddress(program.programId, creatorTokenAddress, seller.publicKey, 1);
+    const fillAccounts = {
+      keeper: keeper.publicKey,
+      owner: seller.publicKey,
+      creator: orderCreator.publicKey,
+      order,
+      ownerAta: getAssociatedTokenAddressSync(mint, seller.publicKey, false, TOKEN_2022_PROGRAM_ID),
+      ...tokenAccounts,
+    };
+    try {
+      await program.methods.fillLimitOrder(0).accountsPartial(fillAccounts).signers([keeper]).rpc();
+      assert.fail("Expected filling below the limit price to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      expect(err.error.errorCode.code).eq("LimitPriceNotReached");
+    }
+
+    // another fan pushes the curve to 15 tokens, 2_500_000 lamports
+    const pusher = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, pusher.publicKey, 5);
+    await buyCreatorToken({ provider, program, decimals: 6, signer: pusher, tokenCreator: orderCreator.publicKey, tokenToMintWholeNumber: 5 });
+
+    const sellerBalance = await provider.connection.getBalance(seller.publicKey, "confirmed");
+    const fillTx = await program.methods.fillLimitOrder(0).accountsPartial(fillAccounts).signers([keeper]).rpc();
+    await checkConfirmTransaction(provider, fillTx);
+
+    expect(await provider.connection.getAccountInfo(order, "confirmed")).eq(null);
+    expect(await provider.connection.getBalance(seller.publicKey, "confirmed")).greaterThan(sellerBalance);
+    const creatorTokenAccount = await program.account.creatorToken.fetch(creatorTokenAddress, "confirmed");
+    expect(creatorTokenAccount.sellOrderEscrow.toNumber()).eq(0);
+    expect(creatorTokenAccount.openLimitOrders).eq(0);
+  });
+  it("Executor runs a fan's DCA plan once per interval", async () => {
+    const dcaCreator = anchor.web3.Keypair.generate();
+    const dcaFan = anchor.web3.Keypair.generate();
+    const executor = anchor.web3.Keypair.generate();
+    for (const wallet of [dcaCreator, dcaFan, executor]) {
+      await airDropSOLAmount(provider, wallet.publicKey, 5);
+    }
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Giorno Giovanna", "https://proof_url.com/")
+      .accounts({ creator: dcaCreator.publicKey })
+      .signers([dcaCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+    const { mint, creatorToken: creatorTokenAddress } = await createCreatorToken({
+      provider,
+      program,
+      signer: dcaCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(1_000_000),
+        slope: new anchor.BN(100_000),
+        name: "Giorno Coin",
+        symbol: "GIORNO",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+
+    const tokenAccounts = getCreatorTokenAccounts(program.programId, dcaCreator.publicKey);
+    const createTx = await program.methods
+      .createDcaPlan(0, new anchor.BN(3_000_000), new anchor.BN(86_400), new anchor.BN(2_000_000), new anchor.BN(10_000_000))
+      .accountsPartial({ owner: dcaFan.publicKey, creator: dcaCreator.publicKey, ...tokenAccounts })
+      .signers([dcaFan])
+      .rpc();
+    await checkConfirmTransaction(provider, createTx);
+
+    const plan = getDcaPlanAddress(program.programId, creatorTokenAddress, dcaFan.publicKey);
+    const executeDca = () =>
+      program.methods
+        .executeDca(0)
+        .accountsPartial({
+          executor: executor.publicKey,
+          owner: dcaFan.publicKey,
+          creator: dcaCreator.publicKey,
+          plan,
+          ...tokenAccounts,
+          ...getHolderHookAccounts(program.programId, mint, dcaFan.publicKey),
+        })
+        .signers([executor])
+        .rpc();
+
+    await checkConfirmTransaction(provider, await executeDca());
+    const planAccount = await program.account.dcaPlan.fetch(plan, "confirmed");
+    const fanAta = getAssociatedTokenAddressSync(mint, dcaFan.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+    const fanTokens = await provider.connection.getTokenAccountBalance(fanAta, "confirmed");
+    expect(fanTokens.value.amount).eq(planAccount.totalBought.toString());
+    expect(planAccount.totalBought.toNumber()).greaterThan(0);
+    expect(planAccount.totalSpent.toNumber()).lessThanOrEqual(3_000_000);
+
+    try {
+      await executeDca();
+      assert.fail("Expected the second buy of the interval to fail");
+    } catch (err) {
+      if (err.name === "AssertionError") {
+        throw err;
+      }
+      expect(err.error.errorCode.code).eq("DcaNotDue");
+    }
+
+    const manageAccounts = { owner: dcaFan.publicKey, plan };
+    await checkConfirmTransaction(provider, await program.methods.topUpDcaPlan(new anchor.BN(1_000_000)).accountsPartial(manageAccounts).signers([dcaFan]).rpc());
+    await checkConfirmTransaction(provider, await program.methods.setDcaPlanPaused(true).accountsPartial(manageAccounts).signers([dcaFan]).rpc());
+    const toppedUp = await program.account.dcaPlan.fetch(plan, "confirmed");
+    expect(toppedUp.balance.toNumber()).eq(planAccount.balance.toNumber() + 1_000_000);
+    expect(toppedUp.paused).eq(true);
+
+    const fanBalance = await provider.connection.getBalance(dcaFan.publicKey, "confirmed");
+    await checkConfirmTransaction(provider, await program.methods.closeDcaPlan().accountsPartial(manageAccounts).signers([dcaFan]).rpc());
+    expect(await provider.connection.getAccountInfo(plan, "confirmed")).eq(null);
+    expect(await provider.connection.getBalance(dcaFan.publicKey, "confirmed")).greaterThan(fanBalance + toppedUp.balance.toNumber() - 10_000);
+  });
+  it("Anti-snipe limits cap buys until the launch window ends", async () => {
+    const launchCreator = anchor.web3.Keypair.generate();
+    const sniper = anchor.web3.Keypair.generate();
+    for (const wallet of [launchCreator, sniper]) {
+      await airDropSOLAmount(provider, wallet.publicKey, 5);
+    }
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Jolyne Cujoh", "https://proof_url.com/")
+      .accounts({ creator: launchCreator.publicKey })
+      .signers([launchCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+    const { creatorToken: creatorTokenAddress } = await createCreatorToken({
+      provider,
+      program,
+      signer: launchCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(1_000_000),
+        slope: new anchor.BN(100_000),
+        name: "Jolyne Coin",
+        symbol: "JOLYNE",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(3_600),
+        antiSnipeMaxBuy: new anchor.BN(2_000_000),
+        antiSnipeMaxWallet: new anchor.BN(3_000_000),
+        presaleMerkleRoot: null,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+
+    const expectFailure = async (tokens: number, code: string) => {
+      try {
+        await buyCreatorToken({ provider, program, decimals: 6, signer: sniper, tokenCreator: launchCreator.publicKey, tokenToMintWholeNumber: tokens });
+        assert.fail(`Expected buying ${tokens} tokens to fail`);
+      } catch (err) {
+        if (err.name === "AssertionError") {
+          throw err;
+        }
+        expect(err.error.errorCode.code).eq(code);
+      }
+    };
+
+    await expectFailure(3, "AntiSnipeBuyLimit");
+    await buyCreatorToken({ provider, program, decimals: 6, signer: sniper, tokenCreator: launchCreator.publicKey, tokenToMintWholeNumber: 2 });
+    await expectFailure(2, "AntiSnipeWalletLimit");
+    await buyCreatorToken({ provider, program, decimals: 6, signer: sniper, tokenCreator: launchCreator.publicKey, tokenToMintWholeNumber: 1 });
+
+    const launchBuys = await program.account.launchBuys.fetch(
+      getLaunchBuysAddress(program.programId, creatorTokenAddress, sniper.publicKey),
+      "confirmed"
+    );
+    expect(launchBuys.amount.toNumber()).eq(3_000_000);
+  });
+  it("Only allowlisted wallets buy in the presale, up to their cap", async () => {
+    const presaleCreator = anchor.web3.Keypair.generate();
+    const [member, outsider] = [anchor.web3.Keypair.generate(), anchor.web3.Keypair.generate()];
+    for (const wallet of [presaleCreator, member, outsider]) {
+      await airDropSOLAmount(provider, wallet.publicKey, 5);
+    }
+
+    const allowlist = buildPresaleAllowlist([
+      { wallet: member.publicKey, cap: new anchor.BN(2_000_000) },
+      { wallet: anchor.web3.Keypair.generate().publicKey, cap: new anchor.BN(5_000_000) },
+      { wallet: anchor.web3.Keypair.generate().publicKey, cap: new anchor.BN(1_000_000) },
+    ]);
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Jotaro Kujo", "https://proof_url.com/")
+      .accounts({ creator: presaleCreator.publicKey })
+      .signers([presaleCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+    await createCreatorToken({
+      provider,
+      program,
+      signer: presaleCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(1_000_000),
+        slope: new anchor.BN(100_000),
+        name: "Jotaro Coin",
+        symbol: "JOTARO",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        // public trading opens in an hour
+        tradingStartsAt: new anchor.BN(Math.floor(Date.now() / 1000) + 3_600),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: allowlist.root,
+        sellTaxBps: 0,
+        sellTaxDecaySeconds: new anchor.BN(0),
+        sellTaxToCreator: false,
+      },
+    });
+
+    const buy = (signer: anchor.web3.Keypair, tokens: number, presale) =>
+      buyCreatorToken({ provider, program, decimals: 6, signer, tokenCreator: presaleCreator.publicKey, tokenToMintWholeNumber: tokens, presale });
+    const expectFailure = async (promise: Promise<unknown>, code: string) => {
+      try {
+        await promise;
+        assert.fail(`Expected the presale buy to fail with ${code}`);
+      } catch (err) {
+        if (err.name === "AssertionError") {
+          throw err;
+        }
+        expect(err.error.errorCode.code).eq(code);
+      }
+    };
+
+    await expectFailure(buy(outsider, 1, null), "PresaleAllocationMissing");
+    await expectFailure(buy(outsider, 1, allowlist.allocation(0)), "InvalidPresaleProof");
+    await buy(member, 1, allowlist.allocation(0));
+    await expectFailure(buy(member, 2, allowlist.allocation(0)), "PresaleCapExceeded");
+    await buy(member, 1, allowlist.allocation(0));
+  });
+  it("Selling right after buying pays the holding-period tax to the creator", async () => {
+    const taxCreator = anchor.web3.Keypair.generate();
+    const flipper = anchor.web3.Keypair.generate();
+    for (const wallet of [taxCreator, flipper]) {
+      await airDropSOLAmount(provider, wallet.publicKey, 5);
+    }
+
+    const identityTx = await program.methods
+      .createCreatorIdentity("Giorno Giovanna", "https://proof_url.com/")
+      .accounts({ creator: taxCreator.publicKey })
+      .signers([taxCreator])
+      .rpc();
+    await checkConfirmTransaction(provider, identityTx);
+    const { mint, creatorToken: creatorTokenAddress } = await createCreatorToken({
+      provider,
+      program,
+      signer: taxCreator,
+      params: {
+        decimals: 6,
+        basePrice: new anchor.BN(1_000_000),
+        slope: new anchor.BN(100_000),
+        name: "Giorno Coin",
+        symbol: "GIORNO",
+        uri: "",
+        royaltyBps: 0,
+        nonTransferable: false,
+        creatorAllocation: new anchor.BN(0),
+        vestingCliffSeconds: new anchor.BN(0),
+        vestingDurationSeconds: new anchor.BN(0),
+        earlyHolderBadges: 0,
+        tradingStartsAt: new anchor.BN(0),
+        antiSnipeSeconds: new anchor.BN(0),
+        antiSnipeMaxBuy: new anchor.BN(0),
+        antiSnipeMaxWallet: new anchor.BN(0),
+        presaleMerkleRoot: null,
+        // 20% on a fresh buy, gone after a day
+        sellTaxBps: 2_000,
+        sellTaxDecaySeconds: new anchor.BN(86_400),
+        sellTaxToCreator: true,
+      },
+    });
+    await buyCreatorToken({ provider, program, decimals: 6, signer: flipper, tokenCreator: taxCreator.publicKey, tokenToMintWholeNumber: 10 });
+
+    const position = getHolderPositionAddress(program.programId, creatorTokenAddress, flipper.publicKey);
+    const positionAccount = await program.account.holderPosition.fetch(position, "confirmed");
+    expect(positionAccount.amount.toNumber()).eq(10_000_000);
+
+    const creatorBalance = await provider.connection.getBalance(taxCreator.publicKey, "confirmed");
+    const sellTx = await program.methods
+      .sellCreatorToken(0, new anchor.BN(5_000_000), null)
+      .accountsPartial({
+        seller: flipper.publicKey,
+        creator: taxCreator.publicKey,
+        position,
+        ...getCreatorTokenAccounts(program.programId, taxCreator.publicKey),
+        ...getHolderHookAccounts(program.programId, mint, flipper.publicKey),
+      })
+      .signers([flipper])
+      .rpc();
+    await checkConfirmTransaction(provider, sellTx);
+
+    // nearly the full 20% of the 5 tokens' return, a few seconds of decay at most
+    const sellReturn = 5 * 1_000_000 + (100_000 * (10 * 10 - 5 * 5)) / 2;
+    const tax = (await provider.connection.getBalance(taxCreator.publicKey, "confirmed")) - creatorBalance;
+    expect(tax).lessThanOrEqual(sellReturn / 5);
+    expect(tax).greaterThan((sellReturn / 5) * 0.99);
+    const remaining = await program.account.holderPosition.fetch(position, "confirmed");
+    expect(remaining.amount.toNumber()).eq(5_000_000);
+
+    // tokens moved to a wallet without a position pay the full 20%, a transfer doesn't reset the tax
+    const receiver = anchor.web3.Keypair.generate();
+    await airDropSOLAmount(provider, receiver.publicKey, 1);
+    const flipperAta = getAssociatedTokenAddressSync(mint, flipper.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const receiverAta = await createAssociatedTokenAccount(
+      provider.connection,
+      receiver,
+      mint,
+      receiver.publicKey,
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+    await transferCheckedWithTransferHook(
+      provider.connection,
+      flipper,
+      flipperAta,
+      mint,
+      receiverAta,
+      flipper,
+      BigInt(5_000_000),
+      6,
+      [],
+      { commitment: "confirmed" },
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    const receiverReturn = await program.methods
+      .getSellingReturnPrice(0, new anchor.BN(5_000_000))
+      .accountsPartial({ creator: taxCreator.publicKey, ...getCreatorTokenAccounts(program.programId, taxCreator.publicKey) })
+      .view();
+    const creatorBalanceBefore = await provider.connection.getBalance(taxCreator.publicKey, "confirmed");
+    const receiverSellTx = await program.methods
+      .sellCreatorToken(0, new anchor.BN(5_000_000), null)
+      .accountsPartial({
+        seller: receiver.publicKey,
+        creator: taxCreator.publicKey,
+        position: getHolderPositionAddress(program.programId, creatorTokenAddress, receiver.publicKey),
+        ...getCreatorTokenAccounts(program.programId, taxCreator.publicKey),
+        ...getHolderHookAccounts(program.programId, mint, receiver.publicKey),
+      })
+      .signers([receiver])
+      .rpc();
+    await checkConfirmTransaction(provider, receiverSellTx);
+    const receiverTax = (await provider.connection.getBalance(taxCreator.publicKey, "confirmed")) - creatorBalanceBefore;
+    expect(receiverTax).eq(Math.floor(receiverReturn.toNumber() / 5));
+  });
+});
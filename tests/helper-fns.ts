@@ -1,6 +1,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import { CreatorToken } from "../target/types/creator_token";
-import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { getAssociatedTokenAddressSync, TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { createHash } from "crypto";
 
 
 export async function checkConfirmTransaction(provider: anchor.Provider, tx: string) {
@@ -31,6 +32,344 @@ export async function airDropSOLAmount(
   return airdropTx;
 }
 
+// Addresses of a creator's token accounts, index 0 keeps the original `[prefix, identity]` seeds
+export function getCreatorTokenAccounts(
+  programId: anchor.web3.PublicKey,
+  tokenCreator: anchor.web3.PublicKey,
+  tokenIndex: number = 0
+) {
+  const [identityProof] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("identity"), tokenCreator.toBuffer()],
+    programId
+  );
+  const indexSeed =
+    tokenIndex === 0
+      ? Buffer.alloc(0)
+      : new anchor.BN(tokenIndex).toArrayLike(Buffer, "le", 2);
+
+  const derive = (prefix: string) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from(prefix), identityProof.toBuffer(), indexSeed],
+      programId
+    )[0];
+
+  return {
+    creatorToken: derive("creator_token"),
+    mint: derive("owner"),
+    vault: derive("vault"),
+  };
+}
+
+export const HOLDER_HOOK_PROGRAM_ID = new anchor.web3.PublicKey(
+  "BrqgrtweUgRuQZW36rtHPgJhHCsMstJANVe6BAJkPZnG"
+);
+
+// Holder hook accounts curve instructions report `owner`'s balance changes to
+export function getHolderHookAccounts(
+  programId: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey,
+  owner: anchor.web3.PublicKey
+) {
+  const [hookAuthority] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("hook_authority")],
+    programId
+  );
+  const [holderRecord] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("holder"), mint.toBuffer(), owner.toBuffer()],
+    HOLDER_HOOK_PROGRAM_ID
+  );
+  const [holderStats] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("holder_stats"), mint.toBuffer()],
+    HOLDER_HOOK_PROGRAM_ID
+  );
+
+  return {
+    holderHookProgram: HOLDER_HOOK_PROGRAM_ID,
+    hookAuthority,
+    holderRecord,
+    holderStats,
+  };
+}
+
+// Transfer hook validation account of a mint, created and closed along with the creator token
+export function getExtraAccountMetaListAddress(mint: anchor.web3.PublicKey) {
+  const [extraAccountMetaList] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("extra-account-metas"), mint.toBuffer()],
+    HOLDER_HOOK_PROGRAM_ID
+  );
+  return extraAccountMetaList;
+}
+
+// Vesting schedule of a creator allocation and the escrow ATA holding it
+export function getVestingAccounts(
+  programId: anchor.web3.PublicKey,
+  creatorToken: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey,
+  tokenProgram: anchor.web3.PublicKey = TOKEN_2022_PROGRAM_ID
+) {
+  const [vesting] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vesting"), creatorToken.toBuffer()],
+    programId
+  );
+  const vestingEscrow = getAssociatedTokenAddressSync(mint, vesting, true, tokenProgram);
+
+  return { vesting, vestingEscrow };
+}
+
+// ProgramData account of an upgradeable program, holds its upgrade authority
+export function getProgramDataAddress(programId: anchor.web3.PublicKey) {
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+  return programData;
+}
+
+// Token-2022 group mint every Token-2022 creator mint is a member of
+export function getPlatformGroupAddress(programId: anchor.web3.PublicKey) {
+  const [groupMint] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("platform_group")],
+    programId
+  );
+  return groupMint;
+}
+
+// remaining_accounts of one buy_many leg, in the order the program reads them
+export function getBuyLegAccounts(
+  programId: anchor.web3.PublicKey,
+  creator: anchor.web3.PublicKey,
+  buyer: anchor.web3.PublicKey,
+  tokenIndex: number = 0,
+  tokenProgram: anchor.web3.PublicKey = TOKEN_2022_PROGRAM_ID
+): anchor.web3.AccountMeta[] {
+  const { creatorToken, mint, vault } = getCreatorTokenAccounts(programId, creator, tokenIndex);
+  const [identityProof] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("identity"), creator.toBuffer()],
+    programId
+  );
+  const [mintAuthority] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("mint_authority"), creatorToken.toBuffer()],
+    programId
+  );
+  const { holderRecord, holderStats } = getHolderHookAccounts(programId, mint, buyer);
+  const buyerAta = getAssociatedTokenAddressSync(mint, buyer, false, tokenProgram);
+  return [
+    { pubkey: identityProof, isSigner: false, isWritable: false },
+    { pubkey: creatorToken, isSigner: false, isWritable: true },
+    { pubkey: mint, isSigner: false, isWritable: true },
+    { pubkey: mintAuthority, isSigner: false, isWritable: false },
+    { pubkey: vault, isSigner: false, isWritable: true },
+    { pubkey: buyerAta, isSigner: false, isWritable: true },
+    { pubkey: holderRecord, isSigner: false, isWritable: true },
+    { pubkey: holderStats, isSigner: false, isWritable: true },
+  ];
+}
+
+export function getLimitOrderAddress(
+  programId: anchor.web3.PublicKey,
+  creatorToken: anchor.web3.PublicKey,
+  owner: anchor.web3.PublicKey,
+  orderId: number
+) {
+  const [order] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("limit_order"), creatorToken.toBuffer(), owner.toBuffer(), new anchor.BN(orderId).toArrayLike(Buffer, "le", 8)],
+    programId
+  );
+  return order;
+}
+
+export function getDcaPlanAddress(
+  programId: anchor.web3.PublicKey,
+  creatorToken: anchor.web3.PublicKey,
+  owner: anchor.web3.PublicKey
+) {
+  const [plan] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("dca_plan"), creatorToken.toBuffer(), owner.toBuffer()],
+    programId
+  );
+  return plan;
+}
+
+export function getLaunchBuysAddress(
+  programId: anchor.web3.PublicKey,
+  creatorToken: anchor.web3.PublicKey,
+  buyer: anchor.web3.PublicKey
+) {
+  const [launchBuys] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("launch_buys"), creatorToken.toBuffer(), buyer.toBuffer()],
+    programId
+  );
+  return launchBuys;
+}
+
+// Leaf of the presale allowlist, sha256(wallet || cap as little endian u64)
+export function presaleLeaf(wallet: anchor.web3.PublicKey, cap: anchor.BN) {
+  return createHash("sha256").update(wallet.toBuffer()).update(cap.toArrayLike(Buffer, "le", 8)).digest();
+}
+
+// Presale allowlist tree with sorted pair hashing, the way the program verifies proofs
+export function buildPresaleAllowlist(entries: { wallet: anchor.web3.PublicKey; cap: anchor.BN }[]) {
+  const hashPair = (a: Buffer, b: Buffer) =>
+    createHash("sha256").update(Buffer.compare(a, b) <= 0 ? Buffer.concat([a, b]) : Buffer.concat([b, a])).digest();
+
+  const levels: Buffer[][] = [entries.map(({ wallet, cap }) => presaleLeaf(wallet, cap))];
+  while (levels[levels.length - 1].length > 1) {
+    const level = levels[levels.length - 1];
+    const next: Buffer[] = [];
+    for (let i = 0; i < level.length; i += 2) {
+      // an odd node out is carried up unchanged
+      next.push(i + 1 < level.length ? hashPair(level[i], level[i + 1]) : level[i]);
+    }
+    levels.push(next);
+  }
+
+  const root = Array.from(levels[levels.length - 1][0]);
+  const allocation = (entryIndex: number) => {
+    const proof: number[][] = [];
+    let index = entryIndex;
+    for (const level of levels.slice(0, -1)) {
+      const sibling = index ^ 1;
+      if (sibling < level.length) {
+        proof.push(Array.from(level[sibling]));
+      }
+      index = Math.floor(index / 2);
+    }
+    return { cap: entries[entryIndex].cap, proof };
+  };
+  return { root, allocation };
+}
+
+export function getHolderPositionAddress(
+  programId: anchor.web3.PublicKey,
+  creatorToken: anchor.web3.PublicKey,
+  holder: anchor.web3.PublicKey
+) {
+  const [position] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("position"), creatorToken.toBuffer(), holder.toBuffer()],
+    programId
+  );
+  return position;
+}
+
+export function getReferrerAddress(programId: anchor.web3.PublicKey, authority: anchor.web3.PublicKey) {
+  const [referrer] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("referrer"), authority.toBuffer()],
+    programId
+  );
+  return referrer;
+}
+
+// Early holder badge of `holder`, only created if they were among the creator token's first buyers
+export function getBadgeAccounts(
+  programId: anchor.web3.PublicKey,
+  creatorToken: anchor.web3.PublicKey,
+  holder: anchor.web3.PublicKey
+) {
+  const [badgeMint] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("badge"), creatorToken.toBuffer(), holder.toBuffer()],
+    programId
+  );
+  const badgeAta = getAssociatedTokenAddressSync(badgeMint, holder, false, TOKEN_2022_PROGRAM_ID);
+  return { badgeMint, badgeAta, badgeTokenProgram: TOKEN_2022_PROGRAM_ID };
+}
+
+// Registry page listing the creator tokens `pageIndex * REGISTRY_PAGE_SIZE` onwards
+export function getRegistryPageAddress(
+  programId: anchor.web3.PublicKey,
+  pageIndex: number
+) {
+  const [registryPage] = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("registry"),
+      new anchor.BN(pageIndex).toArrayLike(Buffer, "le", 8),
+    ],
+    programId
+  );
+  return registryPage;
+}
+
+export type CreateCreatorTokenParams = Parameters<
+  anchor.Program<CreatorToken>["methods"]["createCreatorToken"]
+>[0];
+
+export async function createCreatorToken({
+  provider,
+  program,
+  signer,
+  params,
+  tokenProgram = TOKEN_2022_PROGRAM_ID,
+}: {
+  provider: anchor.Provider;
+  program: anchor.Program<CreatorToken>;
+  signer: anchor.web3.Keypair;
+  params: CreateCreatorTokenParams;
+  tokenProgram?: anchor.web3.PublicKey;
+}) {
+  const [identityProof] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("identity"), signer.publicKey.toBuffer()],
+    program.programId
+  );
+  const [configAddress] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const identity = await program.account.identity.fetch(identityProof, "confirmed");
+  const config = await program.account.config.fetch(configAddress, "confirmed");
+  const registryPageSize = 32;
+
+  const tokenIndex = identity.tokenCount;
+  const tokenAccounts = getCreatorTokenAccounts(program.programId, signer.publicKey, tokenIndex);
+  const creatorAta = getAssociatedTokenAddressSync(
+    tokenAccounts.mint,
+    signer.publicKey,
+    undefined,
+    tokenProgram
+  );
+
+  const extraAccountMetaList = getExtraAccountMetaListAddress(tokenAccounts.mint);
+  const { holderStats } = getHolderHookAccounts(
+    program.programId,
+    tokenAccounts.mint,
+    signer.publicKey
+  );
+
+  // vesting accounts are only passed along with a creator allocation
+  const { vesting, vestingEscrow } = getVestingAccounts(
+    program.programId,
+    tokenAccounts.creatorToken,
+    tokenAccounts.mint,
+    tokenProgram
+  );
+  const hasAllocation = !params.creatorAllocation.isZero();
+  // the group mint and holder hook accounts are only used by Token-2022 mints
+  const isToken2022 = tokenProgram.equals(TOKEN_2022_PROGRAM_ID);
+
+  const createCreatorTokenTx = await program.methods
+    .createCreatorToken(params)
+    .accountsPartial({
+      creator: signer.publicKey,
+      creatorAta,
+      extraAccountMetaList: isToken2022 ? extraAccountMetaList : null,
+      holderStats: isToken2022 ? holderStats : null,
+      holderHookProgram: isToken2022 ? HOLDER_HOOK_PROGRAM_ID : null,
+      groupMint: isToken2022 ? getPlatformGroupAddress(program.programId) : null,
+      vesting: hasAllocation ? vesting : null,
+      vestingEscrow: hasAllocation ? vestingEscrow : null,
+      registryPage: getRegistryPageAddress(
+        program.programId,
+        Math.floor(config.creatorTokenCount.toNumber() / registryPageSize)
+      ),
+      tokenProgram,
+      ...tokenAccounts,
+    })
+    .signers([signer])
+    .rpc();
+
+  await checkConfirmTransaction(provider, createCreatorTokenTx);
+
+  return { createCreatorTokenTx, tokenIndex, ...tokenAccounts };
+}
+
 export async function buyCreatorToken({
   provider,
   program,
@@ -38,6 +377,11 @@ export async function buyCreatorToken({
   signer,
   tokenCreator,
   tokenToMintWholeNumber,
+  tokenIndex = 0,
+  tokenProgram = TOKEN_2022_PROGRAM_ID,
+  referrer = null,
+  expiresAt = null,
+  presale = null,
 }: {
   provider: anchor.Provider;
   program: anchor.Program<CreatorToken>;
@@ -45,18 +389,46 @@ export async function buyCreatorToken({
   signer: anchor.web3.Keypair;
   tokenCreator: anchor.web3.PublicKey;
   tokenToMintWholeNumber: number;
+  tokenIndex?: number;
+  tokenProgram?: anchor.web3.PublicKey;
+  // Referrer account of the affiliate, not their wallet
+  referrer?: anchor.web3.PublicKey | null;
+  // unix timestamp after which the buy is rejected
+  expiresAt?: anchor.BN | null;
+  // allowlist allocation of the signer, for buys before public trading opens
+  presale?: { cap: anchor.BN; proof: number[][] } | null;
 }) {
   const amtOfTokens = tokenToMintWholeNumber;
   const tokenToBuy = new anchor.BN(amtOfTokens).mul(
     new anchor.BN(10).pow(new anchor.BN(decimals))
   );
 
+  const tokenAccounts = getCreatorTokenAccounts(program.programId, tokenCreator, tokenIndex);
+
+  // the per-wallet counter is only needed in the presale and while the anti-snipe window is open
+  const creatorToken = await program.account.creatorToken.fetch(tokenAccounts.creatorToken, "confirmed");
+  const now = Date.now() / 1000;
+  const needsLaunchBuys =
+    now < creatorToken.tradingStartsAt.toNumber() ||
+    (!creatorToken.antiSnipeMaxWallet.isZero() && now < creatorToken.antiSnipeEndsAt.toNumber());
+
   const buyCreatorTokenTx = await program.methods
-    .buyCreatorToken(tokenToBuy)
-    .accounts({
+    .buyCreatorToken(tokenIndex, tokenToBuy, expiresAt, presale)
+    .accountsPartial({
       buyer: signer.publicKey,
       creator: tokenCreator,
-      tokenProgram: TOKEN_2022_PROGRAM_ID,
+      tokenProgram,
+      ...tokenAccounts,
+      ...getHolderHookAccounts(program.programId, tokenAccounts.mint, signer.publicKey),
+      ...getBadgeAccounts(program.programId, tokenAccounts.creatorToken, signer.publicKey),
+      referrer,
+      launchBuys: needsLaunchBuys
+        ? getLaunchBuysAddress(program.programId, tokenAccounts.creatorToken, signer.publicKey)
+        : null,
+      // tokens with a sell tax track when the buyer's tokens were bought
+      position: creatorToken.sellTaxBps > 0
+        ? getHolderPositionAddress(program.programId, tokenAccounts.creatorToken, signer.publicKey)
+        : null,
     })
     .signers([signer])
     .rpc();
@@ -70,15 +442,17 @@ export async function getSellingPriceForToken(
   program: anchor.Program<CreatorToken>,
   amtOfTokens: number,
   decimals: number,
-  tokenCreator: anchor.web3.PublicKey
+  tokenCreator: anchor.web3.PublicKey,
+  tokenIndex: number = 0
 ) {
   const tokenToSell = new anchor.BN(amtOfTokens).mul(
     new anchor.BN(10).pow(new anchor.BN(decimals))
   );
   const lamportsNeeded = await program.methods
-    .getSellingReturnPrice(tokenToSell)
-    .accounts({
+    .getSellingReturnPrice(tokenIndex, tokenToSell)
+    .accountsPartial({
       creator: tokenCreator,
+      ...getCreatorTokenAccounts(program.programId, tokenCreator, tokenIndex),
     })
     .view();
 
@@ -90,15 +464,17 @@ export async function getBuyingPriceForToken(
   program: anchor.Program<CreatorToken>,
   amtOfTokens: number,
   decimals: number,
-  tokenCreator: anchor.web3.PublicKey
+  tokenCreator: anchor.web3.PublicKey,
+  tokenIndex: number = 0
 ) {
   const tokenToBuy = new anchor.BN(amtOfTokens).mul(
     new anchor.BN(10).pow(new anchor.BN(decimals))
   );
   const lamportsNeeded = await program.methods
-    .getBuyingTokenPrice(tokenToBuy)
-    .accounts({
+    .getBuyingTokenPrice(tokenIndex, tokenToBuy)
+    .accountsPartial({
       creator: tokenCreator,
+      ...getCreatorTokenAccounts(program.programId, tokenCreator, tokenIndex),
     })
     .view();
 